@@ -0,0 +1,204 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use clap::{Args, Subcommand};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use zeabur_ops::zeabur::{
+    client::ZeaburClient,
+    service_key::ServiceKey,
+    topology::{DiscoveredService, Topology},
+};
+
+use super::output::LogFormat;
+use super::per_account;
+use super::tail::{self, TailArgs};
+
+#[derive(Subcommand)]
+pub enum LogsCommand {
+    /// Follow the runtime logs of one or more services, merged in timestamp order
+    Tail(TailArgs),
+    /// Print the runtime logs of a service within a time range
+    Export {
+        #[command(flatten)]
+        target: ServiceSelector,
+        /// Start of the range, as an RFC 3339 time or a duration ago such as `1h`
+        #[arg(long, value_parser = parse_time)]
+        since: DateTime<Utc>,
+        /// End of the range, same format as --since, defaults to now
+        #[arg(long, value_parser = parse_time)]
+        until: Option<DateTime<Utc>>,
+        #[arg(short, long, value_enum, default_value = "jsonl")]
+        format: LogFormat,
+    },
+}
+
+// Which service to read the logs of
+#[derive(Args)]
+pub struct ServiceSelector {
+    /// Name or ID of the service
+    #[arg(short, long)]
+    service: String,
+    /// Name or ID of the project, needed when several projects have the service
+    #[arg(short, long)]
+    project: Option<String>,
+    /// Name or ID of the environment, needed when the service runs in several
+    #[arg(short, long)]
+    environment: Option<String>,
+}
+
+// An RFC 3339 time, or a duration before now
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let ago = humantime::parse_duration(value)
+        .map_err(|_| format!("expected an RFC 3339 time or a duration, got {}", value))?;
+    let ago = chrono::Duration::from_std(ago).map_err(|e| e.to_string())?;
+    Ok(Utc::now() - ago)
+}
+
+pub async fn run(clients: &[(String, ZeaburClient)], command: LogsCommand) -> Result<()> {
+    match command {
+        LogsCommand::Tail(args) => tail::run(clients, args).await,
+        LogsCommand::Export {
+            target,
+            since,
+            until,
+            format,
+        } => {
+            let until = until.unwrap_or_else(Utc::now);
+            if since >= until {
+                return Err(anyhow!("--since must be before --until"));
+            }
+            let (account, client, service) = resolve(clients, &target).await?;
+            let logs = client
+                .query_service_runtime_logs_between(&service.key, since, until)
+                .await?;
+            for log in logs {
+                print_line(&account, &service, log.timestamp, &log.log.message, format);
+            }
+            Ok(())
+        }
+    }
+}
+
+// Services visible to the accounts, with the account and client to read
+// each of them with
+pub struct Discovered {
+    pub topology: Topology,
+    pub accounts: HashMap<ServiceKey, (String, ZeaburClient)>,
+}
+
+impl Discovered {
+    pub fn account_of(&self, service: &DiscoveredService) -> (String, ZeaburClient) {
+        self.accounts[&service.key].clone()
+    }
+}
+
+// Discover the services of one project (name or ID), or of every project. A
+// service visible to several accounts is read with the first one.
+pub async fn discover(
+    clients: &[(String, ZeaburClient)],
+    project: Option<&str>,
+) -> Result<Discovered> {
+    let topologies = per_account(clients, |client| async move {
+        Ok(match project {
+            Some(project) => {
+                let projects = client
+                    .list_projects()
+                    .await?
+                    .into_iter()
+                    .filter(|p| p.name == project || p.id == project)
+                    .collect();
+                client.discover_topology_of(projects).await
+            }
+            None => client.discover_topology().await?,
+        })
+    })
+    .await?;
+
+    let mut discovered = Discovered {
+        topology: Topology::default(),
+        accounts: HashMap::new(),
+    };
+    for (account, client, topology) in topologies {
+        for (key, service) in topology.services {
+            if discovered.accounts.contains_key(&key) {
+                continue;
+            }
+            discovered
+                .accounts
+                .insert(key.clone(), (account.clone(), client.clone()));
+            discovered.topology.services.insert(key, service);
+        }
+    }
+    Ok(discovered)
+}
+
+// Find the single service matching the selector
+async fn resolve(
+    clients: &[(String, ZeaburClient)],
+    target: &ServiceSelector,
+) -> Result<(String, ZeaburClient, DiscoveredService)> {
+    let discovered = discover(clients, target.project.as_deref()).await?;
+
+    let matches = discovered.topology.select(
+        target.project.as_deref(),
+        target.environment.as_deref(),
+        &target.service,
+    );
+    match matches.as_slice() {
+        [service] => {
+            let (account, client) = discovered.account_of(service);
+            Ok((account, client, (*service).clone()))
+        }
+        [] => Err(anyhow!("No service named {}", target.service)),
+        candidates => {
+            let names: Vec<String> = candidates
+                .iter()
+                .map(|s| {
+                    format!(
+                        "{}/{}/{}",
+                        s.project_name, s.environment_name, s.service_name
+                    )
+                })
+                .collect();
+            Err(anyhow!(
+                "{} matches several services, narrow it down with --project or --environment: {}",
+                target.service,
+                names.join(", ")
+            ))
+        }
+    }
+}
+
+fn print_line(
+    account: &str,
+    service: &DiscoveredService,
+    timestamp: DateTime<Utc>,
+    message: &str,
+    format: LogFormat,
+) {
+    match format {
+        LogFormat::Text => println!("{} {}", timestamp.to_rfc3339(), message),
+        LogFormat::Jsonl => println!("{}", json_line(account, service, timestamp, message)),
+    }
+}
+
+// A log line with the service it comes from
+pub fn json_line(
+    account: &str,
+    service: &DiscoveredService,
+    timestamp: DateTime<Utc>,
+    message: &str,
+) -> Value {
+    json!({
+        "timestamp": timestamp.to_rfc3339(),
+        "message": message,
+        "account": account,
+        "project_id": service.key.project_id,
+        "environment_id": service.key.environment_id,
+        "service_id": service.key.service_id,
+        "service_name": service.service_name,
+    })
+}
@@ -0,0 +1,106 @@
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use std::future::Future;
+use std::path::PathBuf;
+use zeabur_ops::config::Config;
+use zeabur_ops::zeabur::client::ZeaburClient;
+
+pub mod logs;
+pub mod output;
+pub mod projects;
+pub mod run;
+pub mod services;
+pub mod tail;
+
+#[derive(Parser)]
+#[command(name = "zeabur-ops", version, about)]
+pub struct Cli {
+    /// TOML or YAML config file, the ZEABUR_* environment variables are used when unset
+    #[arg(short, long, env = "ZEABUR_OPS_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Only query the account with this name, all accounts are queried when unset
+    #[arg(short, long, global = true)]
+    pub account: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Collect logs of every configured service and export them (default)
+    Run,
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Inspect the projects of the account
+    #[command(subcommand)]
+    Projects(projects::ProjectsCommand),
+    /// Inspect the services of a project
+    #[command(subcommand)]
+    Services(services::ServicesCommand),
+    /// Read the runtime logs of a service
+    #[command(subcommand)]
+    Logs(logs::LogsCommand),
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Validate the configuration and exit
+    Check,
+}
+
+// Load the config file when given, the environment variables otherwise
+pub fn load_config(path: Option<&PathBuf>) -> Result<Config> {
+    Ok(match path {
+        Some(path) => Config::load(path)?,
+        None => Config::from_env()?,
+    })
+}
+
+// The client of every configured account, or of the given one only
+pub fn clients(config: &Config, account: Option<&str>) -> Result<Vec<(String, ZeaburClient)>> {
+    let selected: Vec<_> = config
+        .accounts
+        .iter()
+        .filter(|a| account.is_none_or(|name| a.name == name))
+        .collect();
+    if selected.is_empty() {
+        return Err(anyhow!("No account named {}", account.unwrap_or_default()));
+    }
+
+    let mut clients = Vec::new();
+    for account in selected {
+        clients.push((account.name.clone(), account.client()?));
+    }
+    Ok(clients)
+}
+
+// Run a query with the client of every account. An account the query fails
+// for is reported and skipped, it is an error only when it fails for all.
+pub async fn per_account<T, F, Fut>(
+    clients: &[(String, ZeaburClient)],
+    query: F,
+) -> Result<Vec<(String, ZeaburClient, T)>>
+where
+    F: Fn(ZeaburClient) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut results = Vec::new();
+    let mut last_error = None;
+    for (account, client) in clients {
+        match query(client.clone()).await {
+            Ok(result) => results.push((account.clone(), client.clone(), result)),
+            Err(e) => {
+                eprintln!("Error querying account {}: {}", account, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    match (results.is_empty(), last_error) {
+        (true, Some(e)) => Err(e),
+        _ => Ok(results),
+    }
+}
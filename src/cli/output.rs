@@ -0,0 +1,59 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+// How listings are printed
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+}
+
+// How log lines are printed
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    // `<timestamp> <message>`
+    Text,
+    // One JSON object per line
+    Jsonl,
+}
+
+// A row of a listing, with the header of its table
+pub trait Row: Serialize {
+    const HEADERS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<String>;
+}
+
+pub fn print_rows<R: Row>(rows: &[R], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(rows)?),
+        OutputFormat::Table => print!("{}", render_table(rows)),
+    }
+    Ok(())
+}
+
+// Left-aligned columns as wide as their widest cell
+fn render_table<R: Row>(rows: &[R]) -> String {
+    let headers: Vec<String> = R::HEADERS.iter().map(|h| h.to_string()).collect();
+    let cells: Vec<Vec<String>> = rows.iter().map(Row::cells).collect();
+
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &cells {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(&headers).chain(&cells) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        table.push_str(line.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
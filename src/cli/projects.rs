@@ -0,0 +1,68 @@
+use anyhow::Result;
+use clap::Subcommand;
+use serde::Serialize;
+use zeabur_ops::zeabur::client::ZeaburClient;
+
+use super::output::{print_rows, OutputFormat, Row};
+use super::per_account;
+
+#[derive(Subcommand)]
+pub enum ProjectsCommand {
+    /// List the projects visible to the API key
+    List {
+        #[arg(short, long, value_enum, default_value = "table")]
+        output: OutputFormat,
+    },
+}
+
+#[derive(Serialize)]
+struct ProjectRow {
+    account: String,
+    id: String,
+    name: String,
+    region: String,
+    environments: Vec<String>,
+}
+
+impl Row for ProjectRow {
+    const HEADERS: &'static [&'static str] = &["ACCOUNT", "ID", "NAME", "REGION", "ENVIRONMENTS"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.account.clone(),
+            self.id.clone(),
+            self.name.clone(),
+            self.region.clone(),
+            self.environments.join(","),
+        ]
+    }
+}
+
+pub async fn run(clients: &[(String, ZeaburClient)], command: ProjectsCommand) -> Result<()> {
+    match command {
+        ProjectsCommand::List { output } => {
+            let listed = per_account(clients, |client| async move {
+                Ok(client.list_projects().await?)
+            })
+            .await?;
+
+            let mut rows = Vec::new();
+            for (account, _, projects) in listed {
+                rows.extend(projects.into_iter().map(|project| {
+                    ProjectRow {
+                        account: account.clone(),
+                        id: project.id,
+                        name: project.name,
+                        region: format!("{}-{}", project.region.provider, project.region.name),
+                        environments: project
+                            .environments
+                            .into_iter()
+                            .map(|environment| environment.name)
+                            .collect(),
+                    }
+                }));
+            }
+            print_rows(&rows, output)
+        }
+    }
+}
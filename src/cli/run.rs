@@ -0,0 +1,235 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use zeabur_ops::config::Config;
+use zeabur_ops::daemon::{
+    collection::{otlp_sink_factory, Collection},
+    discovery::{AccountDiscovery, AccountTopologies},
+    reload::ReloadSignals,
+    supervisor::ShutdownReport,
+};
+use zeabur_ops::log::{checkpoint::open_checkpoint_store, collector_registry::SyncReport};
+
+use super::load_config;
+
+// Exit status when batches could not be stored before the shutdown deadline,
+// they are sent again on the next start since their cursor was not committed
+const EXIT_SHUTDOWN_INCOMPLETE: u8 = 3;
+
+// Wait for SIGTERM (sent by Zeabur and Kubernetes on redeploys) or Ctrl-C
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = terminate.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+pub async fn run(config_path: Option<PathBuf>, config: Config) -> Result<ExitCode> {
+    let mut reloads = ReloadSignals::new(config_path.as_deref())?;
+
+    let (mut collection, mut discovery) = start_collection(config).await?;
+    let mut topologies = discovery.subscribe_topologies();
+    let mut failed_accounts = BTreeMap::new();
+
+    println!("Starting log collection and sinking process...");
+
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => {
+                println!("Shutting down, waiting up to {:?} for in-flight batches", collection.config().shutdown_timeout);
+                discovery.abort();
+
+                // A second signal skips waiting
+                let deadline = collection.config().shutdown_timeout;
+                let report = tokio::select! {
+                    report = collection.shutdown(deadline) => report,
+                    _ = shutdown_signal() => {
+                        eprintln!("Received a second signal, exiting without waiting");
+                        return Ok(ExitCode::from(EXIT_SHUTDOWN_INCOMPLETE));
+                    }
+                };
+                return Ok(shutdown_exit_code(&report));
+            }
+            changed = topologies.changed() => {
+                // Closed once the discovery of every account stopped on a
+                // fatal error such as a rejected API key
+                if changed.is_err() {
+                    break;
+                }
+                let snapshot = topologies.borrow_and_update().clone();
+                for (account, e) in &snapshot.failed {
+                    if failed_accounts.insert(account.clone(), e.clone()).is_none() {
+                        eprintln!("Stopped collecting logs of account {}: {}", account, e);
+                    }
+                }
+                print_report(&collection.sync(&snapshot));
+            }
+            Some(()) = reloads.recv() => {
+                let config = match load_config(config_path.as_ref()) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        eprintln!("Keeping the running config, the new one is invalid: {}", e);
+                        continue;
+                    }
+                };
+                if config == *collection.config() {
+                    continue;
+                }
+                println!("Reloading the config");
+
+                let running = collection.config();
+                if config.checkpoint_store != running.checkpoint_store {
+                    // Every collector depends on the checkpoint store, the new
+                    // one is opened before the running collection is stopped
+                    let next = match new_collection(config).await {
+                        Ok(next) => next,
+                        Err(e) => {
+                            eprintln!("Keeping the running config, the new one cannot be applied: {}", e);
+                            continue;
+                        }
+                    };
+                    discovery.abort();
+                    let deadline = collection.config().shutdown_timeout;
+                    for key in collection.shutdown(deadline).await.aborted {
+                        eprintln!("Gave up waiting for the in-flight batch of {}", key);
+                    }
+                    collection = next;
+                    discovery = discover(&collection);
+                    topologies = discovery.subscribe_topologies();
+                    failed_accounts.clear();
+                    continue;
+                }
+
+                // Accounts whose client changed are discovered again, the
+                // config is applied to the latest topologies of the others
+                let unchanged: Vec<String> = config
+                    .accounts
+                    .iter()
+                    .filter(|account| {
+                        running
+                            .accounts
+                            .iter()
+                            .any(|r| r.name == account.name && r.same_client(account))
+                    })
+                    .map(|account| account.name.clone())
+                    .collect();
+                let rediscover = unchanged.len() != config.accounts.len()
+                    || unchanged.len() != running.accounts.len()
+                    || config.polling.discovery_interval != running.polling.discovery_interval;
+                let mut snapshot = (**topologies.borrow()).clone();
+                snapshot.accounts.retain(|name, _| unchanged.contains(name));
+
+                match collection.reload(config, &snapshot) {
+                    Ok(report) => print_report(&report),
+                    Err(e) => {
+                        eprintln!("Keeping the running config, the new one cannot be applied: {}", e);
+                        continue;
+                    }
+                }
+                if rediscover {
+                    discovery = AccountDiscovery::spawn(
+                        collection.clients().clone(),
+                        collection.config().polling.discovery_interval,
+                        snapshot,
+                    );
+                    topologies = discovery.subscribe_topologies();
+                    failed_accounts.clear();
+                }
+            }
+        }
+    }
+
+    // Services already collected still finish their in-flight batches, the
+    // discovery error is what the exit code reports
+    let deadline = collection.config().shutdown_timeout;
+    print_shutdown_report(&collection.shutdown(deadline).await);
+    let failed: Vec<String> = failed_accounts
+        .iter()
+        .map(|(account, e)| format!("{}: {}", account, e))
+        .collect();
+    Err(anyhow!(
+        "Discovery stopped for every account ({})",
+        failed.join(", ")
+    ))
+}
+
+fn print_shutdown_report(report: &ShutdownReport) {
+    for key in &report.aborted {
+        eprintln!("Gave up waiting for the in-flight batch of {}", key);
+    }
+    for (key, e) in &report.failed {
+        eprintln!("Error flushing the sink of {}: {}", key, e);
+    }
+}
+
+fn shutdown_exit_code(report: &ShutdownReport) -> ExitCode {
+    print_shutdown_report(report);
+    if report.is_clean() {
+        println!("Stopped {} collectors cleanly", report.stopped.len());
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(EXIT_SHUTDOWN_INCOMPLETE)
+    }
+}
+
+// Create the collection of the config and discover its topology in the background
+async fn start_collection(config: Config) -> Result<(Collection, AccountDiscovery)> {
+    let collection = new_collection(config).await?;
+    let discovery = discover(&collection);
+    Ok((collection, discovery))
+}
+
+// Open the checkpoint store of the config and create its collection, nothing
+// is collected until its topology is synced
+async fn new_collection(config: Config) -> Result<Collection> {
+    // Persist collector cursors across restarts when a checkpoint store is configured
+    let checkpoint_store = match &config.checkpoint_store {
+        Some(spec) => Some(open_checkpoint_store(spec).await?),
+        None => None,
+    };
+
+    Collection::new(config, checkpoint_store, otlp_sink_factory())
+}
+
+fn discover(collection: &Collection) -> AccountDiscovery {
+    AccountDiscovery::spawn(
+        collection.clients().clone(),
+        collection.config().polling.discovery_interval,
+        AccountTopologies::default(),
+    )
+}
+
+fn print_report(report: &SyncReport) {
+    for key in &report.added {
+        println!("Started collecting logs for {}", key);
+    }
+    for key in &report.updated {
+        println!("Updated log collection for {}", key);
+    }
+    for key in &report.retired {
+        println!("Stopped collecting logs for {}", key);
+    }
+    for (key, e) in &report.failed {
+        eprintln!("Error setting up log collection for {}: {}", key, e);
+    }
+}
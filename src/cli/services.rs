@@ -0,0 +1,98 @@
+use anyhow::{anyhow, Result};
+use clap::Subcommand;
+use serde::Serialize;
+use zeabur_ops::zeabur::client::ZeaburClient;
+
+use super::output::{print_rows, OutputFormat, Row};
+use super::per_account;
+
+#[derive(Subcommand)]
+pub enum ServicesCommand {
+    /// List the services of a project, per environment
+    List {
+        /// Name or ID of the project
+        #[arg(short, long)]
+        project: String,
+        /// Only list the services of this environment (name or ID)
+        #[arg(short, long)]
+        environment: Option<String>,
+        #[arg(short, long, value_enum, default_value = "table")]
+        output: OutputFormat,
+    },
+}
+
+#[derive(Serialize)]
+struct ServiceRow {
+    account: String,
+    project_id: String,
+    project_name: String,
+    environment_id: String,
+    environment_name: String,
+    service_id: String,
+    service_name: String,
+    status: Option<String>,
+}
+
+impl Row for ServiceRow {
+    const HEADERS: &'static [&'static str] = &["ENVIRONMENT", "SERVICE", "SERVICE ID", "STATUS"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.environment_name.clone(),
+            self.service_name.clone(),
+            self.service_id.clone(),
+            self.status.clone().unwrap_or_else(|| "-".to_string()),
+        ]
+    }
+}
+
+pub async fn run(clients: &[(String, ZeaburClient)], command: ServicesCommand) -> Result<()> {
+    match command {
+        ServicesCommand::List {
+            project,
+            environment,
+            output,
+        } => {
+            // The project is listed with the first account which has it
+            let listed = per_account(clients, |client| async move {
+                Ok(client.list_projects().await?)
+            })
+            .await?;
+            let (account, client, project) = listed
+                .into_iter()
+                .find_map(|(account, client, projects)| {
+                    let project = projects
+                        .into_iter()
+                        .find(|p| p.name == project || p.id == project)?;
+                    Some((account, client, project))
+                })
+                .ok_or_else(|| anyhow!("No project named {}", project))?;
+
+            let topology = client.discover_topology_of(vec![project]).await;
+            if let Some(e) = topology.failed_projects.values().next() {
+                return Err(anyhow!("Error listing the services: {}", e));
+            }
+
+            let rows: Vec<ServiceRow> = topology
+                .services
+                .into_values()
+                .filter(|s| {
+                    environment
+                        .as_deref()
+                        .is_none_or(|e| s.environment_name == e || s.key.environment_id == e)
+                })
+                .map(|s| ServiceRow {
+                    account: account.clone(),
+                    project_id: s.key.project_id,
+                    project_name: s.project_name,
+                    environment_id: s.key.environment_id,
+                    environment_name: s.environment_name,
+                    service_id: s.key.service_id,
+                    service_name: s.service_name,
+                    status: s.latest_deployment.status,
+                })
+                .collect();
+            print_rows(&rows, output)
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use clap::{Args, ValueEnum};
+use regex::Regex;
+use serde_json::Value;
+use std::future::Future;
+use std::io::IsTerminal;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use zeabur_ops::log::{
+    log_collector::LogCollector,
+    log_entry::{LogEntry, LogSource},
+    zeabur_log_collector::{CollectorMode, ZeaburServiceLogCollector},
+};
+use zeabur_ops::zeabur::{client::ZeaburClient, topology::DiscoveredService};
+
+use super::logs::{discover, json_line, parse_time};
+use super::output::LogFormat;
+
+// Pages walked back per service and poll while catching up with --since
+const SINCE_PAGE_BUDGET: usize = 100;
+
+// ANSI colours given to the services in turn
+const PALETTE: &[&str] = &[
+    "\x1b[32m", "\x1b[33m", "\x1b[34m", "\x1b[35m", "\x1b[36m", "\x1b[92m", "\x1b[93m", "\x1b[94m",
+    "\x1b[95m", "\x1b[96m",
+];
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ColorMode {
+    // Colour when stdout is a terminal and NO_COLOR is unset
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Args)]
+pub struct TailArgs {
+    /// Name or ID of a service to follow, repeat or separate with commas to
+    /// follow several. Every environment running the service is followed.
+    #[arg(short, long = "service", required = true, value_delimiter = ',')]
+    services: Vec<String>,
+    /// Only follow services of this project (name or ID)
+    #[arg(short, long)]
+    project: Option<String>,
+    /// Only follow services of this environment (name or ID)
+    #[arg(short, long)]
+    environment: Option<String>,
+    /// Start with the logs since this RFC 3339 time or duration ago such as
+    /// `10m`, instead of the latest window
+    #[arg(long, value_parser = parse_time)]
+    since: Option<DateTime<Utc>>,
+    /// Only print lines matching one of these regular expressions
+    #[arg(short, long)]
+    include: Vec<Regex>,
+    /// Skip lines matching one of these regular expressions
+    #[arg(short = 'x', long)]
+    exclude: Vec<Regex>,
+    /// Time between two polls
+    #[arg(long, default_value = "2s", value_parser = humantime::parse_duration)]
+    interval: Duration,
+    #[arg(short, long, value_enum, default_value = "text")]
+    format: LogFormat,
+    /// Indent messages which are JSON objects or arrays
+    #[arg(long, conflicts_with = "format")]
+    pretty: bool,
+    #[arg(long, value_enum, default_value = "auto")]
+    color: ColorMode,
+}
+
+// A followed service and how its lines are prefixed
+struct Followed {
+    account: String,
+    service: DiscoveredService,
+    collector: Arc<ZeaburServiceLogCollector>,
+    prefix: String,
+}
+
+pub async fn run(clients: &[(String, ZeaburClient)], args: TailArgs) -> Result<()> {
+    let services = resolve_all(clients, &args).await?;
+    let colored = match args.color {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => {
+            std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none()
+        }
+    };
+    let mode = match args.since {
+        Some(since) => CollectorMode::Backfill {
+            since: Some(since),
+            page_budget: SINCE_PAGE_BUDGET,
+        },
+        None => CollectorMode::Tail,
+    };
+
+    let names = prefixes(services.iter().map(|(_, _, service)| service));
+    let width = names
+        .iter()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let followed: Vec<Followed> = services
+        .into_iter()
+        .zip(names)
+        .enumerate()
+        .map(|(index, ((account, client, service), name))| {
+            let collector = ZeaburServiceLogCollector::new(
+                service.key.project_id.clone(),
+                service.key.environment_id.clone(),
+                service.key.service_id.clone(),
+                client.clone(),
+            )
+            .with_mode(mode.clone())
+            .with_source(LogSource::new(&account, &service));
+            let prefix = match colored {
+                true => format!(
+                    "{}{:<width$}{}",
+                    PALETTE[index % PALETTE.len()],
+                    name,
+                    RESET,
+                    width = width
+                ),
+                false => format!("{:<width$}", name, width = width),
+            };
+            Followed {
+                account,
+                service,
+                collector: Arc::new(collector),
+                prefix,
+            }
+        })
+        .collect();
+
+    let mut interrupt = interrupted()?;
+
+    loop {
+        for (index, entry) in poll_all(&followed).await {
+            if args.matches(&entry.message) {
+                args.print(&followed[index], &entry);
+            }
+        }
+
+        tokio::select! {
+            _ = &mut interrupt => return Ok(()),
+            _ = tokio::time::sleep(args.interval) => {}
+        }
+    }
+}
+
+impl TailArgs {
+    fn matches(&self, message: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|re| re.is_match(message)))
+            && !self.exclude.iter().any(|re| re.is_match(message))
+    }
+
+    fn print(&self, followed: &Followed, entry: &LogEntry) {
+        if self.format == LogFormat::Jsonl {
+            println!(
+                "{}",
+                json_line(
+                    &followed.account,
+                    &followed.service,
+                    entry.timestamp,
+                    &entry.message
+                )
+            );
+            return;
+        }
+
+        let message = match self.pretty {
+            true => pretty_json(&entry.message).unwrap_or_else(|| entry.message.clone()),
+            false => entry.message.clone(),
+        };
+        for (i, line) in message.lines().enumerate() {
+            match i {
+                0 => println!(
+                    "{} {} {}",
+                    followed.prefix,
+                    entry.timestamp.to_rfc3339(),
+                    line
+                ),
+                _ => println!("{} {}", followed.prefix, line),
+            }
+        }
+    }
+}
+
+// Listen for Ctrl-C right away, so one sent during the first poll is not missed
+fn interrupted() -> Result<Pin<Box<dyn Future<Output = ()> + Send>>> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut interrupt = signal(SignalKind::interrupt())?;
+        Ok(Box::pin(async move {
+            interrupt.recv().await;
+        }))
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(Box::pin(async {
+            let _ = tokio::signal::ctrl_c().await;
+        }))
+    }
+}
+
+// Poll every service at once and merge their batches in timestamp order.
+// Lines are only ordered within a poll, a service lagging behind may still
+// print lines older than the ones of the previous poll.
+async fn poll_all(followed: &[Followed]) -> Vec<(usize, LogEntry)> {
+    let mut polls = JoinSet::new();
+    for (index, followed) in followed.iter().enumerate() {
+        let collector = followed.collector.clone();
+        polls.spawn(async move {
+            let logs = collector.collect_logs().await;
+            if logs.is_ok() {
+                collector.commit(None).await?;
+            }
+            logs.map(|logs| (index, logs))
+        });
+    }
+
+    let mut merged = Vec::new();
+    while let Some(poll) = polls.join_next().await {
+        match poll {
+            Ok(Ok((index, logs))) => merged.extend(logs.into_iter().map(|log| (index, log))),
+            Ok(Err(e)) => eprintln!("Error polling logs: {}", e),
+            Err(e) => eprintln!("Error polling logs: {}", e),
+        }
+    }
+    merged.sort_by_key(|(index, log)| (log.timestamp, *index));
+    merged
+}
+
+// Every service matching one of the names, in topology order, with the
+// account and client to follow it with
+async fn resolve_all(
+    clients: &[(String, ZeaburClient)],
+    args: &TailArgs,
+) -> Result<Vec<(String, ZeaburClient, DiscoveredService)>> {
+    let discovered = discover(clients, args.project.as_deref()).await?;
+
+    let mut services: Vec<&DiscoveredService> = Vec::new();
+    for name in &args.services {
+        let matches =
+            discovered
+                .topology
+                .select(args.project.as_deref(), args.environment.as_deref(), name);
+        if matches.is_empty() {
+            return Err(anyhow!("No service named {}", name));
+        }
+        for service in matches {
+            if !services.iter().any(|s| s.key == service.key) {
+                services.push(service);
+            }
+        }
+    }
+    services.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(services
+        .into_iter()
+        .map(|service| {
+            let (account, client) = discovered.account_of(service);
+            (account, client, service.clone())
+        })
+        .collect())
+}
+
+// The shortest name telling the services apart: the service name, prefixed
+// by the environment and project when they differ
+fn prefixes<'a>(services: impl Iterator<Item = &'a DiscoveredService>) -> Vec<String> {
+    let services: Vec<&DiscoveredService> = services.collect();
+    let differ = |field: fn(&DiscoveredService) -> &str| {
+        services.iter().any(|s| {
+            field(s)
+                != services
+                    .first()
+                    .map(|first| field(first))
+                    .unwrap_or_default()
+        })
+    };
+    let projects = differ(|s| &s.project_name);
+    let environments = projects || differ(|s| &s.environment_name);
+
+    services
+        .iter()
+        .map(|s| match (projects, environments) {
+            (true, _) => format!(
+                "{}/{}/{}",
+                s.project_name, s.environment_name, s.service_name
+            ),
+            (false, true) => format!("{}/{}", s.environment_name, s.service_name),
+            (false, false) => s.service_name.clone(),
+        })
+        .collect()
+}
+
+// The message indented, when it is a JSON object or array
+fn pretty_json(message: &str) -> Option<String> {
+    let trimmed = message.trim();
+    if !trimmed.starts_with('{') && !trimmed.starts_with('[') {
+        return None;
+    }
+    let value: Value = serde_json::from_str(trimmed).ok()?;
+    serde_json::to_string_pretty(&value).ok()
+}
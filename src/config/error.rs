@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+use thiserror::Error;
+
+// Errors raised while loading the configuration
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Unsupported config file {0}, expected a .toml, .yaml or .yml file")]
+    UnknownFormat(PathBuf),
+
+    #[error("Failed to parse config: {0}")]
+    Parse(String),
+
+    // A value at the given path could not be interpolated or deserialized
+    #[error("Invalid config at {path}: {message}")]
+    Field { path: String, message: String },
+
+    // Every problem found by validation, so they can be fixed in one go
+    #[error("Invalid config:\n{}", .0.iter().map(|problem| format!("  - {}", problem)).collect::<Vec<_>>().join("\n"))]
+    Invalid(Vec<String>),
+}
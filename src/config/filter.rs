@@ -0,0 +1,99 @@
+use globset::{Glob, GlobMatcher};
+use serde::Deserialize;
+
+use crate::zeabur::topology::DiscoveredService;
+
+// Glob matched against both the name and the ID of a project, environment or service
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct NamePattern {
+    pattern: String,
+    matcher: GlobMatcher,
+}
+
+impl NamePattern {
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn matches(&self, name: &str, id: &str) -> bool {
+        self.matcher.is_match(name) || self.matcher.is_match(id)
+    }
+}
+
+impl TryFrom<String> for NamePattern {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        let matcher = Glob::new(&pattern)
+            .map_err(|e| format!("invalid glob pattern {:?}: {}", pattern, e.kind()))?
+            .compile_matcher();
+        Ok(Self { pattern, matcher })
+    }
+}
+
+impl PartialEq for NamePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+// Patterns for each level of the topology. An empty list places no
+// constraint on its level.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceFilter {
+    #[serde(default)]
+    pub projects: Vec<NamePattern>,
+    #[serde(default)]
+    pub environments: Vec<NamePattern>,
+    #[serde(default)]
+    pub services: Vec<NamePattern>,
+}
+
+impl ServiceFilter {
+    pub fn is_empty(&self) -> bool {
+        self.projects.is_empty() && self.environments.is_empty() && self.services.is_empty()
+    }
+
+    // Whether the service matches every level that has patterns, used for includes
+    pub fn matches_all(&self, service: &DiscoveredService) -> bool {
+        self.levels(service)
+            .iter()
+            .all(|(patterns, name, id)| patterns.is_empty() || any_matches(patterns, name, id))
+    }
+
+    // Whether the service matches any level, used for excludes
+    pub fn matches_any(&self, service: &DiscoveredService) -> bool {
+        self.levels(service)
+            .iter()
+            .any(|(patterns, name, id)| any_matches(patterns, name, id))
+    }
+
+    fn levels<'a>(
+        &'a self,
+        service: &'a DiscoveredService,
+    ) -> [(&'a [NamePattern], &'a str, &'a str); 3] {
+        [
+            (
+                &self.projects,
+                &service.project_name,
+                &service.key.project_id,
+            ),
+            (
+                &self.environments,
+                &service.environment_name,
+                &service.key.environment_id,
+            ),
+            (
+                &self.services,
+                &service.service_name,
+                &service.key.service_id,
+            ),
+        ]
+    }
+}
+
+fn any_matches(patterns: &[NamePattern], name: &str, id: &str) -> bool {
+    patterns.iter().any(|pattern| pattern.matches(name, id))
+}
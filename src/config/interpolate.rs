@@ -0,0 +1,88 @@
+use serde_json::Value;
+
+use super::error::ConfigError;
+
+// Replace `${VAR}` and `${VAR:-default}` in every string of the config with
+// the value of the environment variable, `$$` stands for a literal `$`
+pub fn interpolate(
+    value: &mut Value,
+    env: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigError> {
+    interpolate_at(value, "", env)
+}
+
+fn interpolate_at(
+    value: &mut Value,
+    path: &str,
+    env: &impl Fn(&str) -> Option<String>,
+) -> Result<(), ConfigError> {
+    match value {
+        Value::String(text) => {
+            *text = interpolate_str(text, env).map_err(|message| ConfigError::Field {
+                path: if path.is_empty() {
+                    ".".to_string()
+                } else {
+                    path.to_string()
+                },
+                message,
+            })?;
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                interpolate_at(item, &format!("{}[{}]", path, i), env)?;
+            }
+        }
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                let path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                interpolate_at(field, &path, env)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn interpolate_str(text: &str, env: &impl Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        if let Some(after) = rest.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or_else(|| format!("unterminated `${{` in {:?}", text))?;
+            let expression = &after[..end];
+            let (name, default) = match expression.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (expression, None),
+            };
+            if name.is_empty() {
+                return Err(format!("empty variable name in {:?}", text));
+            }
+            match (env(name), default) {
+                (Some(value), _) => result.push_str(&value),
+                (None, Some(default)) => result.push_str(default),
+                (None, None) => {
+                    return Err(format!("environment variable {} is not set", name));
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+        }
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
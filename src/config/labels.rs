@@ -0,0 +1,92 @@
+use serde::Deserialize;
+
+use crate::zeabur::topology::DiscoveredService;
+
+// Placeholders which can be used in label templates
+pub const PLACEHOLDERS: &[&str] = &[
+    "account",
+    "project_name",
+    "project_id",
+    "environment_name",
+    "environment_id",
+    "service_name",
+    "service_id",
+    "region",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Placeholder(String),
+}
+
+// Label value such as "{project_name}-{environment_name}", rendered per service
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LabelTemplate {
+    parts: Vec<Part>,
+}
+
+impl LabelTemplate {
+    // Render for a service discovered with the given account
+    pub fn render(&self, account: &str, service: &DiscoveredService) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Placeholder(name) => placeholder_value(name, account, service),
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<String> for LabelTemplate {
+    type Error = String;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        let mut parts = Vec::new();
+        let mut rest = template.as_str();
+
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Literal(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unterminated placeholder in {:?}", template))?;
+            let name = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(format!(
+                    "unknown placeholder {{{}}} in {:?}, expected one of {}",
+                    name,
+                    template,
+                    PLACEHOLDERS.join(", ")
+                ));
+            }
+            parts.push(Part::Placeholder(name.to_string()));
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+
+        Ok(Self { parts })
+    }
+}
+
+fn placeholder_value(name: &str, account: &str, service: &DiscoveredService) -> String {
+    match name {
+        "account" => account.to_string(),
+        "project_name" => service.project_name.clone(),
+        "project_id" => service.key.project_id.clone(),
+        "environment_name" => service.environment_name.clone(),
+        "environment_id" => service.key.environment_id.clone(),
+        "service_name" => service.service_name.clone(),
+        "service_id" => service.key.service_id.clone(),
+        "region" => format!(
+            "{}-{}-{}",
+            service.region.provider, service.region.name, service.region.id
+        ),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,900 @@
+pub mod error;
+pub mod filter;
+pub mod interpolate;
+pub mod labels;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use self::error::ConfigError;
+use self::filter::ServiceFilter;
+use self::labels::LabelTemplate;
+use crate::daemon::supervisor::PollSettings;
+use crate::log::log_entry::LogSeverity;
+use crate::log::multiline::{MultilinePreset, MultilineRule};
+use crate::log::parse::{BodyParser, ParseFormat, ParseTarget, PromotedFields};
+use crate::log::processor::{
+    drop_processor::DropProcessor, enrich_processor::EnrichProcessor,
+    filter_processor::FilterProcessor, log_match::LogMatch, map_processor::MapProcessor,
+    route_processor::RouteProcessor, BuiltinProcessor,
+};
+use crate::log::severity::{LinePattern, SeverityClassifier, SeverityRule};
+use crate::log::sink::otlp_log_sink::OtlpHttpOptions;
+use crate::log::zeabur_log_collector::CollectorMode;
+use crate::zeabur::client::ZeaburClient;
+use crate::zeabur::error::ZeaburError;
+use crate::zeabur::rate_limit::{OperationLimit, RateLimits};
+use crate::zeabur::topology::DiscoveredService;
+
+// Format of a config file, picked from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    pub fn from_path(path: &Path) -> Result<Self, ConfigError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(Self::Toml),
+            Some("yaml") | Some("yml") => Ok(Self::Yaml),
+            _ => Err(ConfigError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+}
+
+// Everything zeabur-ops needs to run, see zeabur-ops.example.toml
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub accounts: Vec<AccountConfig>,
+    #[serde(default)]
+    pub polling: PollingConfig,
+    // Where to persist log cursors, e.g. sqlite:/data/cursors.db
+    #[serde(default)]
+    pub checkpoint_store: Option<String>,
+    #[serde(default)]
+    pub backfill: BackfillConfig,
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+    #[serde(default)]
+    pub pipelines: Vec<PipelineConfig>,
+    #[serde(default)]
+    pub multiline: Vec<MultilineConfig>,
+    #[serde(default)]
+    pub parsers: Vec<ParserConfig>,
+    #[serde(default)]
+    pub severity: SeverityConfig,
+    #[serde(default)]
+    pub processors: Vec<ProcessorConfig>,
+    // How long in-flight batches may take to be stored on shutdown
+    #[serde(default = "default_shutdown_timeout", with = "humantime_serde")]
+    pub shutdown_timeout: Duration,
+}
+
+// A Zeabur API key and the part of its topology to collect from
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig {
+    #[serde(default = "default_name")]
+    pub name: String,
+    pub api_key: String,
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub include: ServiceFilter,
+    #[serde(default)]
+    pub exclude: ServiceFilter,
+    // Budget of API calls made with this key, on top of the defaults
+    #[serde(default)]
+    pub rate_limits: RateLimitsConfig,
+}
+
+impl AccountConfig {
+    // A service is collected when it matches the includes and none of the excludes
+    pub fn accepts(&self, service: &DiscoveredService) -> bool {
+        self.include.matches_all(service) && !self.exclude.matches_any(service)
+    }
+
+    // A client of its own, so accounts do not share a rate limit
+    pub fn client(&self) -> Result<ZeaburClient, ZeaburError> {
+        let mut client_builder = ZeaburClient::builder(self.api_key.clone())
+            .rate_limits(self.rate_limits.apply(RateLimits::default()));
+        if let Some(endpoint) = &self.endpoint {
+            client_builder = client_builder.endpoint(endpoint.clone());
+        }
+        client_builder.build()
+    }
+
+    // Whether both build the same client, filters may differ
+    pub fn same_client(&self, other: &AccountConfig) -> bool {
+        self.api_key == other.api_key
+            && self.endpoint == other.endpoint
+            && self.rate_limits == other.rate_limits
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitsConfig {
+    pub discovery: OperationLimitConfig,
+    pub logs: OperationLimitConfig,
+}
+
+impl RateLimitsConfig {
+    pub fn apply(&self, defaults: RateLimits) -> RateLimits {
+        RateLimits {
+            discovery: self.discovery.apply(defaults.discovery),
+            logs: self.logs.apply(defaults.logs),
+        }
+    }
+}
+
+// Overrides of one operation budget, see OperationLimit
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OperationLimitConfig {
+    pub requests_per_second: Option<f64>,
+    pub burst: Option<u32>,
+    pub max_concurrency: Option<usize>,
+}
+
+impl OperationLimitConfig {
+    pub fn apply(&self, defaults: OperationLimit) -> OperationLimit {
+        OperationLimit {
+            requests_per_second: self
+                .requests_per_second
+                .unwrap_or(defaults.requests_per_second),
+            burst: self.burst.unwrap_or(defaults.burst),
+            max_concurrency: self.max_concurrency.unwrap_or(defaults.max_concurrency),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PollingConfig {
+    #[serde(with = "humantime_serde")]
+    pub min_interval: Duration,
+    #[serde(with = "humantime_serde")]
+    pub max_interval: Duration,
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    pub max_concurrent_polls: usize,
+    #[serde(with = "humantime_serde")]
+    pub discovery_interval: Duration,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        let poll = PollSettings::default();
+        Self {
+            min_interval: poll.min_interval,
+            max_interval: poll.max_interval,
+            timeout: poll.timeout,
+            max_concurrent_polls: 8,
+            discovery_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackfillConfig {
+    // Walk back through missed logs; only the latest window is read when off
+    pub enabled: bool,
+    // Where to start for services without a checkpoint
+    pub since: Option<DateTime<Utc>>,
+    // Maximum number of runtimeLogs pages fetched per poll while catching up
+    pub pages: usize,
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            since: None,
+            pages: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkKind {
+    OtlpHttp,
+}
+
+// A destination for logs, referenced by name from pipelines
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SinkConfig {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: SinkKind,
+    // Falls back to the OTEL_EXPORTER_OTLP_* environment variables when unset
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default = "default_sink_timeout", with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+impl SinkConfig {
+    pub fn otlp_http_options(&self) -> OtlpHttpOptions {
+        OtlpHttpOptions {
+            endpoint: self.endpoint.clone(),
+            headers: self.headers.clone(),
+            timeout: self.timeout,
+        }
+    }
+}
+
+// Where the logs of the selected services go and how they are labelled. A
+// service uses the first pipeline that selects it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineConfig {
+    pub name: String,
+    #[serde(default)]
+    pub select: ServiceFilter,
+    // Names of the sinks to export to, all sinks when empty
+    #[serde(default)]
+    pub sinks: Vec<String>,
+    // Extra labels, rendered from templates such as "{project_name}"
+    #[serde(default)]
+    pub labels: BTreeMap<String, LabelTemplate>,
+    #[serde(default, with = "humantime_serde")]
+    pub min_interval: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    pub max_interval: Option<Duration>,
+    #[serde(default, with = "humantime_serde")]
+    pub timeout: Option<Duration>,
+}
+
+impl PipelineConfig {
+    pub fn render_labels(
+        &self,
+        account: &str,
+        service: &DiscoveredService,
+    ) -> HashMap<String, String> {
+        self.labels
+            .iter()
+            .map(|(name, template)| (name.clone(), template.render(account, service)))
+            .collect()
+    }
+}
+
+// Combining of multiline entries such as stack traces for the selected
+// services, see MultilineRule. A service uses the first rule that selects it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MultilineConfig {
+    #[serde(default)]
+    pub select: ServiceFilter,
+    // Continuation rule of a language, the patterns below take precedence
+    #[serde(default)]
+    pub preset: Option<MultilinePreset>,
+    #[serde(default)]
+    pub start: Option<LinePattern>,
+    #[serde(default)]
+    pub continuation: Option<LinePattern>,
+    #[serde(default)]
+    pub max_lines: Option<usize>,
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+    #[serde(default, with = "humantime_serde")]
+    pub flush_timeout: Option<Duration>,
+}
+
+// Parsing of the JSON or logfmt messages of the selected services, see
+// BodyParser. A service uses the first parser that selects it, the messages
+// of services no parser selects are sent as they are.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParserConfig {
+    #[serde(default)]
+    pub select: ServiceFilter,
+    #[serde(default)]
+    pub format: ParseFormat,
+    #[serde(default)]
+    pub into: ParseTarget,
+    #[serde(default)]
+    pub promote: PromotedFields,
+}
+
+// How the severity of lines is told, see SeverityClassifier
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SeverityConfig {
+    // Detect the severity from common log formats and crash reports
+    pub detect: bool,
+    pub overrides: Vec<SeverityOverride>,
+}
+
+impl Default for SeverityConfig {
+    fn default() -> Self {
+        Self {
+            detect: true,
+            overrides: Vec::new(),
+        }
+    }
+}
+
+// Severity settings of the selected services. A service uses the first
+// override that selects it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeverityOverride {
+    #[serde(default)]
+    pub select: ServiceFilter,
+    // Tried in order before the detection
+    #[serde(default)]
+    pub rules: Vec<SeverityRule>,
+    #[serde(default)]
+    pub detect: Option<bool>,
+    // Severity of the lines nothing matched
+    #[serde(default)]
+    pub default: Option<LogSeverity>,
+}
+
+// A processing step of the selected services, run after parsing and severity
+// detection. Every processor selecting a service applies, in order. `match`
+// selects the entries a processor applies to, all of them when empty.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ProcessorConfig {
+    Filter {
+        #[serde(default)]
+        select: ServiceFilter,
+        #[serde(default, rename = "match")]
+        when: LogMatch,
+    },
+    Drop {
+        #[serde(default)]
+        select: ServiceFilter,
+        #[serde(default, rename = "match")]
+        when: LogMatch,
+    },
+    Map {
+        #[serde(default)]
+        select: ServiceFilter,
+        #[serde(default, rename = "match")]
+        when: LogMatch,
+        pattern: LinePattern,
+        #[serde(default)]
+        replacement: String,
+        #[serde(default)]
+        attribute: Option<String>,
+    },
+    Enrich {
+        #[serde(default)]
+        select: ServiceFilter,
+        #[serde(default, rename = "match")]
+        when: LogMatch,
+        // Rendered like pipeline labels
+        attributes: BTreeMap<String, LabelTemplate>,
+    },
+    Route {
+        #[serde(default)]
+        select: ServiceFilter,
+        #[serde(default, rename = "match")]
+        when: LogMatch,
+        sinks: Vec<String>,
+        #[serde(default)]
+        copy: bool,
+    },
+}
+
+impl ProcessorConfig {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Filter { .. } => "filter",
+            Self::Drop { .. } => "drop",
+            Self::Map { .. } => "map",
+            Self::Enrich { .. } => "enrich",
+            Self::Route { .. } => "route",
+        }
+    }
+
+    pub fn select(&self) -> &ServiceFilter {
+        match self {
+            Self::Filter { select, .. }
+            | Self::Drop { select, .. }
+            | Self::Map { select, .. }
+            | Self::Enrich { select, .. }
+            | Self::Route { select, .. } => select,
+        }
+    }
+
+    // The processor of a service discovered with the given account
+    pub fn build(&self, account: &str, service: &DiscoveredService) -> BuiltinProcessor {
+        match self.clone() {
+            Self::Filter { when, .. } => BuiltinProcessor::Filter(FilterProcessor { when }),
+            Self::Drop { when, .. } => BuiltinProcessor::Drop(DropProcessor { when }),
+            Self::Map {
+                when,
+                pattern,
+                replacement,
+                attribute,
+                ..
+            } => BuiltinProcessor::Map(MapProcessor {
+                when,
+                pattern,
+                replacement,
+                attribute,
+            }),
+            Self::Enrich {
+                when, attributes, ..
+            } => BuiltinProcessor::Enrich(EnrichProcessor {
+                when,
+                attributes: attributes
+                    .iter()
+                    .map(|(name, template)| (name.clone(), template.render(account, service)))
+                    .collect(),
+            }),
+            Self::Route {
+                when, sinks, copy, ..
+            } => BuiltinProcessor::Route(RouteProcessor { when, sinks, copy }),
+        }
+    }
+}
+
+fn default_name() -> String {
+    "default".to_string()
+}
+
+fn default_sink_timeout() -> Duration {
+    Duration::from_secs(3)
+}
+
+fn default_shutdown_timeout() -> Duration {
+    Duration::from_secs(20)
+}
+
+impl Config {
+    // Read, interpolate and validate a TOML or YAML config file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let format = ConfigFormat::from_path(path)?;
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: PathBuf::from(path),
+            source,
+        })?;
+        Self::parse(&text, format)
+    }
+
+    pub fn parse(text: &str, format: ConfigFormat) -> Result<Self, ConfigError> {
+        Self::parse_with_env(text, format, |name| std::env::var(name).ok())
+    }
+
+    // Parse with the given lookup used for `${VAR}` interpolation
+    pub fn parse_with_env(
+        text: &str,
+        format: ConfigFormat,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut value = match format {
+            ConfigFormat::Toml => toml::from_str::<toml::Value>(text)
+                .map(toml_to_json)
+                .map_err(|e| ConfigError::Parse(e.to_string()))?,
+            ConfigFormat::Yaml => serde_yaml::from_str::<Value>(text)
+                .map_err(|e| ConfigError::Parse(e.to_string()))?,
+        };
+        interpolate::interpolate(&mut value, &env)?;
+
+        let config: Config =
+            serde_path_to_error::deserialize(value).map_err(|e| ConfigError::Field {
+                path: e.path().to_string(),
+                message: e.inner().to_string(),
+            })?;
+        config.normalized().validated()
+    }
+
+    // Build the config from the ZEABUR_* environment variables, for
+    // deployments which do not use a config file
+    pub fn from_env() -> Result<Self, ConfigError> {
+        fn env_value<T: std::str::FromStr>(key: &str) -> Result<Option<T>, ConfigError>
+        where
+            T::Err: std::fmt::Display,
+        {
+            match std::env::var(key) {
+                Ok(value) => value.parse().map(Some).map_err(|e| ConfigError::Field {
+                    path: key.to_string(),
+                    message: format!("invalid value {:?}: {}", value, e),
+                }),
+                Err(_) => Ok(None),
+            }
+        }
+        let env_secs = |key: &str, default: Duration| -> Result<Duration, ConfigError> {
+            Ok(env_value(key)?.map_or(default, Duration::from_secs))
+        };
+
+        let api_key = std::env::var("ZEABUR_API_KEY").map_err(|_| ConfigError::Field {
+            path: "ZEABUR_API_KEY".to_string(),
+            message: "environment variable not found".to_string(),
+        })?;
+        let polling = PollingConfig::default();
+        let backfill = BackfillConfig::default();
+
+        let config = Config {
+            accounts: vec![AccountConfig {
+                name: default_name(),
+                api_key,
+                endpoint: std::env::var("ZEABUR_API_ENDPOINT").ok(),
+                include: ServiceFilter::default(),
+                exclude: ServiceFilter::default(),
+                rate_limits: RateLimitsConfig::default(),
+            }],
+            polling: PollingConfig {
+                min_interval: env_secs("ZEABUR_OPS_POLL_MIN_INTERVAL", polling.min_interval)?,
+                max_interval: env_secs("ZEABUR_OPS_POLL_MAX_INTERVAL", polling.max_interval)?,
+                timeout: env_secs("ZEABUR_OPS_POLL_TIMEOUT", polling.timeout)?,
+                max_concurrent_polls: env_value("ZEABUR_OPS_MAX_CONCURRENT_POLLS")?
+                    .unwrap_or(polling.max_concurrent_polls),
+                discovery_interval: env_secs(
+                    "ZEABUR_OPS_DISCOVERY_INTERVAL",
+                    polling.discovery_interval,
+                )?,
+            },
+            checkpoint_store: std::env::var("ZEABUR_OPS_CHECKPOINT_STORE").ok(),
+            backfill: BackfillConfig {
+                enabled: env_value("ZEABUR_OPS_BACKFILL_ENABLED")?.unwrap_or(backfill.enabled),
+                since: env_value("ZEABUR_OPS_BACKFILL_SINCE")?,
+                pages: env_value("ZEABUR_OPS_BACKFILL_PAGES")?.unwrap_or(backfill.pages),
+            },
+            sinks: Vec::new(),
+            pipelines: Vec::new(),
+            multiline: Vec::new(),
+            parsers: Vec::new(),
+            severity: SeverityConfig::default(),
+            processors: Vec::new(),
+            shutdown_timeout: env_secs("ZEABUR_OPS_SHUTDOWN_TIMEOUT", default_shutdown_timeout())?,
+        };
+        config.normalized().validated()
+    }
+
+    // Without sinks or pipelines, every service is exported to the OTLP
+    // endpoint from the environment
+    fn normalized(mut self) -> Self {
+        if self.sinks.is_empty() {
+            self.sinks.push(SinkConfig {
+                name: default_name(),
+                kind: SinkKind::OtlpHttp,
+                endpoint: None,
+                headers: HashMap::new(),
+                timeout: default_sink_timeout(),
+            });
+        }
+        if self.pipelines.is_empty() {
+            self.pipelines.push(PipelineConfig {
+                name: default_name(),
+                select: ServiceFilter::default(),
+                sinks: Vec::new(),
+                labels: BTreeMap::new(),
+                min_interval: None,
+                max_interval: None,
+                timeout: None,
+            });
+        }
+        self
+    }
+
+    fn validated(self) -> Result<Self, ConfigError> {
+        self.validate()?;
+        Ok(self)
+    }
+
+    // Check the references and bounds which cannot be expressed by the types
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.accounts.is_empty() {
+            problems.push("at least one account must be configured".to_string());
+        }
+        check_unique(
+            "accounts",
+            self.accounts.iter().map(|a| a.name.as_str()),
+            &mut problems,
+        );
+        for (i, account) in self.accounts.iter().enumerate() {
+            if account.api_key.trim().is_empty() {
+                problems.push(format!("accounts[{}].api_key must not be empty", i));
+            }
+            for (operation, limit) in [
+                ("discovery", &account.rate_limits.discovery),
+                ("logs", &account.rate_limits.logs),
+            ] {
+                let section = format!("accounts[{}].rate_limits.{}", i, operation);
+                if limit
+                    .requests_per_second
+                    .is_some_and(|rate| !(rate.is_finite() && rate > 0.0))
+                {
+                    problems.push(format!(
+                        "{}.requests_per_second must be a finite number greater than zero",
+                        section
+                    ));
+                }
+                if limit.max_concurrency == Some(0) {
+                    problems.push(format!("{}.max_concurrency must be at least 1", section));
+                }
+            }
+        }
+
+        let polling = &self.polling;
+        check_intervals(
+            "polling",
+            polling.min_interval,
+            polling.max_interval,
+            polling.timeout,
+            &mut problems,
+        );
+        if polling.max_concurrent_polls == 0 {
+            problems.push("polling.max_concurrent_polls must be at least 1".to_string());
+        }
+        if polling.discovery_interval.is_zero() {
+            problems.push("polling.discovery_interval must be greater than zero".to_string());
+        }
+
+        if self
+            .checkpoint_store
+            .as_deref()
+            .is_some_and(|spec| spec.trim().is_empty())
+        {
+            problems.push("checkpoint_store must not be empty".to_string());
+        }
+        if self.backfill.pages == 0 {
+            problems.push("backfill.pages must be at least 1".to_string());
+        }
+
+        check_unique(
+            "sinks",
+            self.sinks.iter().map(|s| s.name.as_str()),
+            &mut problems,
+        );
+        check_unique(
+            "pipelines",
+            self.pipelines.iter().map(|p| p.name.as_str()),
+            &mut problems,
+        );
+        for (i, pipeline) in self.pipelines.iter().enumerate() {
+            for sink in &pipeline.sinks {
+                if !self.sinks.iter().any(|s| &s.name == sink) {
+                    problems.push(format!(
+                        "pipelines[{}] ({}) refers to unknown sink {:?}",
+                        i, pipeline.name, sink
+                    ));
+                }
+            }
+            let poll = self.poll_settings(pipeline);
+            check_intervals(
+                &format!("pipelines[{}] ({})", i, pipeline.name),
+                poll.min_interval,
+                poll.max_interval,
+                poll.timeout,
+                &mut problems,
+            );
+        }
+
+        for (i, multiline) in self.multiline.iter().enumerate() {
+            if multiline.preset.is_none()
+                && multiline.start.is_none()
+                && multiline.continuation.is_none()
+            {
+                problems.push(format!(
+                    "multiline[{}] needs a preset, a start or a continuation pattern",
+                    i
+                ));
+            }
+            if multiline.max_lines == Some(0) {
+                problems.push(format!("multiline[{}].max_lines must be at least 1", i));
+            }
+            if multiline.max_bytes == Some(0) {
+                problems.push(format!("multiline[{}].max_bytes must be at least 1", i));
+            }
+        }
+
+        for (i, processor) in self.processors.iter().enumerate() {
+            let section = format!("processors[{}] ({})", i, processor.name());
+            match processor {
+                ProcessorConfig::Filter { when, .. } | ProcessorConfig::Drop { when, .. }
+                    if when.is_empty() =>
+                {
+                    problems.push(format!("{} needs a match", section));
+                }
+                ProcessorConfig::Enrich { attributes, .. } if attributes.is_empty() => {
+                    problems.push(format!("{} needs attributes", section));
+                }
+                ProcessorConfig::Route { sinks, .. } => {
+                    if sinks.is_empty() {
+                        problems.push(format!("{} needs sinks", section));
+                    }
+                    for sink in sinks {
+                        if !self.sinks.iter().any(|s| &s.name == sink) {
+                            problems.push(format!("{} refers to unknown sink {:?}", section, sink));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    // The pipeline handling a service, if any selects it
+    pub fn pipeline_for(&self, service: &DiscoveredService) -> Option<&PipelineConfig> {
+        self.pipelines
+            .iter()
+            .find(|pipeline| pipeline.select.matches_all(service))
+    }
+
+    pub fn sinks_of(&self, pipeline: &PipelineConfig) -> Vec<&SinkConfig> {
+        self.sinks
+            .iter()
+            .filter(|sink| pipeline.sinks.is_empty() || pipeline.sinks.contains(&sink.name))
+            .collect()
+    }
+
+    // Global polling settings with the overrides of the pipeline applied
+    pub fn poll_settings(&self, pipeline: &PipelineConfig) -> PollSettings {
+        PollSettings {
+            min_interval: pipeline.min_interval.unwrap_or(self.polling.min_interval),
+            max_interval: pipeline.max_interval.unwrap_or(self.polling.max_interval),
+            timeout: pipeline.timeout.unwrap_or(self.polling.timeout),
+            ..PollSettings::default()
+        }
+    }
+
+    // The processors selecting a service, in order
+    pub fn processors_for(
+        &self,
+        account: &str,
+        service: &DiscoveredService,
+    ) -> Vec<BuiltinProcessor> {
+        self.processors
+            .iter()
+            .filter(|processor| processor.select().matches_all(service))
+            .map(|processor| processor.build(account, service))
+            .collect()
+    }
+
+    // The sinks entries of a service may be routed to
+    pub fn route_sinks(&self, processors: &[BuiltinProcessor]) -> Vec<SinkConfig> {
+        self.sinks
+            .iter()
+            .filter(|sink| {
+                processors.iter().any(|processor| match processor {
+                    BuiltinProcessor::Route(route) => route.sinks.contains(&sink.name),
+                    _ => false,
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn multiline_rule(&self, service: &DiscoveredService) -> Option<MultilineRule> {
+        let multiline = self
+            .multiline
+            .iter()
+            .find(|multiline| multiline.select.matches_all(service))?;
+        let defaults = MultilineRule::default();
+        Some(MultilineRule {
+            start: multiline.start.clone(),
+            continuation: multiline
+                .continuation
+                .clone()
+                .or_else(|| multiline.preset.map(|preset| preset.continuation())),
+            max_lines: multiline.max_lines.unwrap_or(defaults.max_lines),
+            max_bytes: multiline.max_bytes.unwrap_or(defaults.max_bytes),
+            flush_timeout: multiline.flush_timeout.unwrap_or(defaults.flush_timeout),
+        })
+    }
+
+    pub fn body_parser(&self, service: &DiscoveredService) -> Option<BodyParser> {
+        let parser = self
+            .parsers
+            .iter()
+            .find(|parser| parser.select.matches_all(service))?;
+        Some(BodyParser {
+            format: parser.format,
+            target: parser.into,
+            promote: parser.promote.clone(),
+        })
+    }
+
+    // The severity settings of a service, with its override applied
+    pub fn severity_classifier(&self, service: &DiscoveredService) -> SeverityClassifier {
+        let mut classifier = SeverityClassifier {
+            detect: self.severity.detect,
+            ..SeverityClassifier::default()
+        };
+        if let Some(found) = self
+            .severity
+            .overrides
+            .iter()
+            .find(|found| found.select.matches_all(service))
+        {
+            classifier.rules = found.rules.clone();
+            classifier.detect = found.detect.unwrap_or(classifier.detect);
+            classifier.default = found.default.unwrap_or(classifier.default);
+        }
+        classifier
+    }
+
+    pub fn collector_mode(&self) -> CollectorMode {
+        if !self.backfill.enabled {
+            return CollectorMode::Tail;
+        }
+        CollectorMode::Backfill {
+            since: self.backfill.since,
+            page_budget: self.backfill.pages,
+        }
+    }
+}
+
+// Helper function to report names used more than once
+fn check_unique<'a>(
+    section: &str,
+    names: impl Iterator<Item = &'a str>,
+    problems: &mut Vec<String>,
+) {
+    let mut seen = HashSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            problems.push(format!("{} contains {:?} more than once", section, name));
+        }
+    }
+}
+
+// Helper function to check the bounds of polling settings
+fn check_intervals(
+    section: &str,
+    min_interval: Duration,
+    max_interval: Duration,
+    timeout: Duration,
+    problems: &mut Vec<String>,
+) {
+    if min_interval.is_zero() {
+        problems.push(format!(
+            "{}: min_interval must be greater than zero",
+            section
+        ));
+    }
+    if min_interval > max_interval {
+        problems.push(format!(
+            "{}: min_interval ({:?}) must not be greater than max_interval ({:?})",
+            section, min_interval, max_interval
+        ));
+    }
+    if timeout.is_zero() {
+        problems.push(format!("{}: timeout must be greater than zero", section));
+    }
+}
+
+// TOML dates are turned into strings, everything else maps one to one
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+use crate::log::log_collector::PollLoad;
+
+// Poll interval of one service, kept between a minimum and a maximum. It is
+// halved when the service looks saturated and grows by half when polls come
+// back empty, so idle services cost few API calls and chatty ones catch up fast.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveInterval {
+    min: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl AdaptiveInterval {
+    const BACKOFF_FACTOR: f64 = 1.5;
+    // Lowest minimum, a zero one would never grow again and poll in a busy loop
+    pub const MIN_INTERVAL: Duration = Duration::from_millis(10);
+
+    // Start at the minimum, so a new service is caught up with quickly
+    pub fn new(min: Duration, max: Duration) -> Self {
+        let min = min.max(Self::MIN_INTERVAL);
+        Self {
+            min,
+            max: max.max(min),
+            current: min,
+        }
+    }
+
+    pub fn current(&self) -> Duration {
+        self.current
+    }
+
+    // Adjust the interval to the load of the last poll and return it
+    pub fn observe(&mut self, load: PollLoad) -> Duration {
+        let next = match load {
+            PollLoad::Saturated => self.current / 2,
+            PollLoad::Steady => self.current,
+            PollLoad::Idle => self.current.mul_f64(Self::BACKOFF_FACTOR),
+        };
+        self.current = next.clamp(self.min, self.max);
+        self.current
+    }
+}
@@ -0,0 +1,459 @@
+use anyhow::Error;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::discovery::AccountTopologies;
+use super::supervisor::{PollSettings, ShutdownReport, Supervisor};
+use crate::config::{Config, SinkConfig};
+use crate::log::{
+    checkpoint_store::CheckpointStore,
+    collector_registry::{CollectorRegistry, ServiceTarget, SyncReport},
+    log_entry::LogSource,
+    log_processor::LogProcessor,
+    log_sink::LogSink,
+    multiline::MultilineRule,
+    parse::BodyParser,
+    processor::BuiltinProcessor,
+    severity::SeverityClassifier,
+    sink::{
+        fanout_log_sink::FanoutLogSink, multiline_log_sink::MultilineLogSink,
+        otlp_log_sink::OtlpLogSink, processing_log_sink::ProcessingLogSink,
+    },
+    zeabur_log_collector::ZeaburServiceLogCollector,
+};
+use crate::zeabur::{client::ZeaburClient, service_key::ServiceKey};
+
+// Builds the sink of a service out of the sinks of its pipeline and its labels
+pub type SinkFactory = Box<
+    dyn Fn(&[SinkConfig], HashMap<String, String>) -> Result<Arc<dyn LogSink>, Error> + Send + Sync,
+>;
+
+// Export to every configured OTLP HTTP sink
+pub fn otlp_sink_factory() -> SinkFactory {
+    Box::new(|sinks, labels| {
+        let mut built: Vec<Arc<dyn LogSink>> = Vec::new();
+        for sink in sinks {
+            built.push(Arc::new(OtlpLogSink::new_http_with(
+                &sink.otlp_http_options(),
+                labels.clone(),
+            )?));
+        }
+
+        Ok(match built.len() {
+            1 => built.remove(0),
+            _ => Arc::new(FanoutLogSink::new(built)),
+        })
+    })
+}
+
+// What a running service was started with, compared on every sync to tell
+// which sinks and pollers have to be rebuilt
+#[derive(Debug, Clone, PartialEq)]
+struct ServicePlan {
+    account: String,
+    sink: SinkPlan,
+    poll: PollSettings,
+}
+
+// Everything the sink of a service is built from, the sink is rebuilt when
+// any of it changes
+#[derive(Debug, Clone, PartialEq)]
+struct SinkPlan {
+    sinks: Vec<SinkConfig>,
+    multiline: Option<MultilineRule>,
+    parser: Option<BodyParser>,
+    severity: SeverityClassifier,
+    processors: Vec<BuiltinProcessor>,
+    // Sinks the processors route entries to
+    routes: Vec<SinkConfig>,
+}
+
+type Registry = CollectorRegistry<Arc<ZeaburServiceLogCollector>, Arc<dyn LogSink>>;
+
+// The collectors, sinks and pollers of every service selected by the config.
+// Collectors are kept as long as their service stays selected, so their
+// cursors survive topology changes and config reloads.
+pub struct Collection {
+    config: Config,
+    // One client per account, by account name
+    clients: BTreeMap<String, ZeaburClient>,
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    make_sink: SinkFactory,
+    registry: Registry,
+    supervisor: Supervisor,
+    plans: HashMap<ServiceKey, ServicePlan>,
+}
+
+impl Collection {
+    pub fn new(
+        config: Config,
+        checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+        make_sink: SinkFactory,
+    ) -> Result<Self, Error> {
+        let mut clients = BTreeMap::new();
+        for account in &config.accounts {
+            clients.insert(account.name.clone(), account.client()?);
+        }
+
+        let supervisor = Supervisor::new(config.polling.max_concurrent_polls);
+        Ok(Self {
+            config,
+            clients,
+            checkpoint_store,
+            make_sink,
+            registry: Registry::new(),
+            supervisor,
+            plans: HashMap::new(),
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn clients(&self) -> &BTreeMap<String, ZeaburClient> {
+        &self.clients
+    }
+
+    pub fn collector(&self, key: &ServiceKey) -> Option<Arc<ZeaburServiceLogCollector>> {
+        self.registry.get(key).map(|entry| entry.collector.clone())
+    }
+
+    pub fn sink(&self, key: &ServiceKey) -> Option<Arc<dyn LogSink>> {
+        self.registry.get(key).map(|entry| entry.sink.clone())
+    }
+
+    pub fn is_running(&self, key: &ServiceKey) -> bool {
+        self.supervisor.is_running(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.registry.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registry.is_empty()
+    }
+
+    // Swap in a new config and apply it to the current topologies. Services
+    // whose account and pipeline did not change keep running untouched, the
+    // ones of an account whose client changed get a new collector.
+    pub fn reload(
+        &mut self,
+        config: Config,
+        topologies: &AccountTopologies,
+    ) -> Result<SyncReport, Error> {
+        // Build every new client first, so a failure leaves the collection as is
+        let mut clients = BTreeMap::new();
+        let mut kept = HashSet::new();
+        for account in &config.accounts {
+            let running = self.config.accounts.iter().find(|r| r.name == account.name);
+            let client = match (running, self.clients.get(&account.name)) {
+                (Some(running), Some(client)) if running.same_client(account) => {
+                    kept.insert(account.name.clone());
+                    client.clone()
+                }
+                _ => account.client()?,
+            };
+            clients.insert(account.name.clone(), client);
+        }
+
+        // Collectors keep the client they were built with
+        let replaced: Vec<ServiceKey> = self
+            .plans
+            .iter()
+            .filter(|(_, plan)| !kept.contains(&plan.account))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &replaced {
+            self.retire(key);
+        }
+
+        let concurrency_changed =
+            config.polling.max_concurrent_polls != self.config.polling.max_concurrent_polls;
+        self.config = config;
+        self.clients = clients;
+
+        // The concurrency limit is shared by all pollers, so they all move
+        // over to a new supervisor
+        if concurrency_changed {
+            self.supervisor = Supervisor::new(self.config.polling.max_concurrent_polls);
+            self.plans.clear();
+        }
+
+        let mut report = self.sync(topologies);
+        report_replaced(&mut report, replaced);
+        Ok(report)
+    }
+
+    // Start, restart and stop collectors so they match the topologies
+    pub fn sync(&mut self, topologies: &AccountTopologies) -> SyncReport {
+        let (targets, plans, sources) = self.plan(topologies);
+
+        // A service now collected with another account needs a collector
+        // using the client of that account
+        let moved: Vec<ServiceKey> = plans
+            .iter()
+            .filter(|(key, plan)| {
+                self.plans
+                    .get(*key)
+                    .is_some_and(|running| running.account != plan.account)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &moved {
+            self.retire(key);
+        }
+
+        let Self {
+            clients,
+            checkpoint_store,
+            make_sink,
+            registry,
+            supervisor,
+            config,
+            ..
+        } = self;
+        let build_sink = |target: &ServiceTarget| {
+            let plan = &plans[&target.key].sink;
+            let sink = make_sink(&plan.sinks, target.labels.clone())?;
+            let mut routes = BTreeMap::new();
+            for route in &plan.routes {
+                routes.insert(
+                    route.name.clone(),
+                    make_sink(std::slice::from_ref(route), target.labels.clone())?,
+                );
+            }
+
+            // Parsed first, so a promoted level wins over the detected one,
+            // and the configured processors see both
+            let mut processors: Vec<Arc<dyn LogProcessor>> = Vec::new();
+            if let Some(parser) = &plan.parser {
+                processors.push(Arc::new(parser.clone()));
+            }
+            processors.push(Arc::new(plan.severity.clone()));
+            for processor in &plan.processors {
+                processors.push(Arc::new(processor.clone()));
+            }
+            let sink: Arc<dyn LogSink> =
+                Arc::new(ProcessingLogSink::new(sink, processors).with_routes(routes));
+
+            // Lines are combined first, parsing and detection see whole entries
+            Ok(match &plan.multiline {
+                Some(rule) => Arc::new(MultilineLogSink::new(sink, rule.clone())),
+                None => sink,
+            })
+        };
+
+        let previous: Vec<(ServiceKey, Arc<dyn LogSink>)> = registry
+            .iter()
+            .map(|entry| (entry.target.key.clone(), entry.sink.clone()))
+            .collect();
+
+        let mut report = registry.sync(
+            targets,
+            |target| {
+                let client = &clients[&plans[&target.key].account];
+                let collector = ZeaburServiceLogCollector::new(
+                    target.key.project_id.clone(),
+                    target.key.environment_id.clone(),
+                    target.key.service_id.clone(),
+                    client.clone(),
+                )
+                .with_mode(config.collector_mode());
+                Ok(Arc::new(match checkpoint_store {
+                    Some(store) => collector.with_checkpoint_store(store.clone()),
+                    None => collector,
+                }))
+            },
+            build_sink,
+        );
+
+        // Sinks whose plan changed while their labels did not
+        let stale_sinks: Vec<ServiceKey> = plans
+            .iter()
+            .filter(|(key, plan)| {
+                self.plans
+                    .get(*key)
+                    .is_some_and(|running| running.sink != plan.sink)
+                    && !report.updated.contains(key)
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        let rebuilt = registry.rebuild_sinks(&stale_sinks, build_sink);
+        report.updated.extend(rebuilt.updated);
+        report.updated.sort();
+        report.failed.extend(rebuilt.failed);
+
+        for key in &report.retired {
+            supervisor.stop(key);
+            self.plans.remove(key);
+        }
+
+        // Sinks may hold entries back, e.g. multiline ones waiting for more lines
+        for (key, sink) in previous {
+            if !registry
+                .get(&key)
+                .is_some_and(|entry| Arc::ptr_eq(&entry.sink, &sink))
+            {
+                flush_in_background(key, sink);
+            }
+        }
+
+        // Names and deployments may change without restarting anything
+        for (key, source) in sources {
+            if let Some(entry) = registry.get(&key) {
+                entry.collector.set_source(source);
+            }
+        }
+
+        // A service whose sink failed to build keeps its previous plan, so
+        // the rebuild is tried again on the next sync
+        for (key, plan) in plans {
+            if report.failed.iter().any(|(failed, _)| failed == &key) {
+                continue;
+            }
+            let Some(entry) = registry.get(&key) else {
+                continue;
+            };
+            if self.plans.get(&key) != Some(&plan) || !supervisor.is_running(&key) {
+                supervisor.start(
+                    key.clone(),
+                    entry.collector.clone(),
+                    entry.sink.clone(),
+                    plan.poll.clone(),
+                );
+                self.plans.insert(key, plan);
+            }
+        }
+
+        report_replaced(&mut report, moved);
+        report
+    }
+
+    // Stop polling, wait for in-flight batches to be stored and committed, and
+    // flush the sinks, all within the deadline
+    pub async fn shutdown(&mut self, deadline: Duration) -> ShutdownReport {
+        let deadline = tokio::time::Instant::now() + deadline;
+        let mut report = self
+            .supervisor
+            .shutdown(deadline.saturating_duration_since(tokio::time::Instant::now()))
+            .await;
+        self.plans.clear();
+
+        for entry in self.registry.iter() {
+            match tokio::time::timeout_at(deadline, entry.sink.flush()).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => report
+                    .failed
+                    .push((entry.target.key.clone(), e.to_string())),
+                Err(_) => report
+                    .failed
+                    .push((entry.target.key.clone(), "flush timed out".to_string())),
+            }
+        }
+        report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+
+        report
+    }
+
+    // Stop a service and drop its collector, the next sync starts it afresh
+    fn retire(&mut self, key: &ServiceKey) {
+        self.supervisor.stop(key);
+        if let Some(entry) = self.registry.remove(key) {
+            flush_in_background(entry.target.key, entry.sink);
+        }
+        self.plans.remove(key);
+    }
+
+    // The targets selected by the config, with the plan of each of them. A
+    // service visible to several accounts is collected with the first one
+    // accepting it.
+    fn plan(
+        &self,
+        topologies: &AccountTopologies,
+    ) -> (
+        Vec<ServiceTarget>,
+        HashMap<ServiceKey, ServicePlan>,
+        HashMap<ServiceKey, LogSource>,
+    ) {
+        let mut targets = Vec::new();
+        let mut plans = HashMap::new();
+        let mut sources = HashMap::new();
+
+        for account in &self.config.accounts {
+            let Some(topology) = topologies.accounts.get(&account.name) else {
+                continue;
+            };
+            if !self.clients.contains_key(&account.name) {
+                continue;
+            }
+
+            for service in topology.services.values() {
+                if !account.accepts(service) || plans.contains_key(&service.key) {
+                    continue;
+                }
+                let Some(pipeline) = self.config.pipeline_for(service) else {
+                    continue;
+                };
+
+                let source = LogSource::new(&account.name, service);
+                let processors = self.config.processors_for(&account.name, service);
+                let mut labels = source.labels();
+                labels.extend(pipeline.render_labels(&account.name, service));
+                sources.insert(service.key.clone(), source);
+                targets.push(ServiceTarget {
+                    key: service.key.clone(),
+                    labels,
+                });
+                plans.insert(
+                    service.key.clone(),
+                    ServicePlan {
+                        account: account.name.clone(),
+                        sink: SinkPlan {
+                            sinks: self
+                                .config
+                                .sinks_of(pipeline)
+                                .into_iter()
+                                .cloned()
+                                .collect(),
+                            multiline: self.config.multiline_rule(service),
+                            parser: self.config.body_parser(service),
+                            severity: self.config.severity_classifier(service),
+                            routes: self.config.route_sinks(&processors),
+                            processors,
+                        },
+                        poll: self.config.poll_settings(pipeline),
+                    },
+                );
+            }
+        }
+
+        (targets, plans, sources)
+    }
+}
+
+// Store what a sink which is no longer used still holds
+fn flush_in_background(key: ServiceKey, sink: Arc<dyn LogSink>) {
+    tokio::spawn(async move {
+        if let Err(e) = sink.flush().await {
+            log::warn!("Error flushing the previous sink of {}: {}", key, e);
+        }
+    });
+}
+
+// Services whose collector was dropped ahead of a sync are reported as updated
+// when the sync started them again, and as retired when it did not
+fn report_replaced(report: &mut SyncReport, replaced: Vec<ServiceKey>) {
+    for key in replaced {
+        if let Some(i) = report.added.iter().position(|added| added == &key) {
+            report.added.remove(i);
+            report.updated.push(key);
+        } else if !report.failed.iter().any(|(failed, _)| failed == &key) {
+            report.retired.push(key);
+        }
+    }
+    report.updated.sort();
+    report.retired.sort();
+}
@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::zeabur::client::ZeaburClient;
+use crate::zeabur::error::ZeaburError;
+use crate::zeabur::service_key::ServiceKey;
+use crate::zeabur::topology::{DiscoveredService, Topology};
+
+// Change between two consecutive topology snapshots
+#[derive(Debug, Clone, PartialEq)]
+pub enum TopologyEvent {
+    Added(Box<DiscoveredService>),
+    Removed(ServiceKey),
+    Changed {
+        previous: Box<DiscoveredService>,
+        current: Box<DiscoveredService>,
+    },
+}
+
+// Compute the events leading from one snapshot to the next
+pub fn diff_topology(previous: &Topology, current: &Topology) -> Vec<TopologyEvent> {
+    let mut events = Vec::new();
+
+    for (key, service) in &previous.services {
+        match current.services.get(key) {
+            None => events.push(TopologyEvent::Removed(key.clone())),
+            Some(current) if current != service => events.push(TopologyEvent::Changed {
+                previous: Box::new(service.clone()),
+                current: Box::new(current.clone()),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (key, service) in &current.services {
+        if !previous.services.contains_key(key) {
+            events.push(TopologyEvent::Added(Box::new(service.clone())));
+        }
+    }
+
+    events
+}
+
+// Services of projects which failed to be listed are taken from the previous
+// snapshot, so a flaky API call does not look like the services went away
+fn carry_over_failed_projects(previous: &Topology, current: &mut Topology) {
+    for (key, service) in &previous.services {
+        if current.failed_projects.contains_key(&key.project_id) {
+            current
+                .services
+                .entry(key.clone())
+                .or_insert_with(|| service.clone());
+        }
+    }
+}
+
+// Background task refreshing the topology on its own interval
+pub struct DiscoveryTask {
+    topology: watch::Receiver<Arc<Topology>>,
+    events: broadcast::Sender<TopologyEvent>,
+    handle: JoinHandle<Result<(), ZeaburError>>,
+}
+
+impl DiscoveryTask {
+    pub fn spawn(client: ZeaburClient, refresh_interval: Duration) -> Self {
+        let (topology_tx, topology) = watch::channel(Arc::new(Topology::default()));
+        let (events, _) = broadcast::channel(1024);
+        let handle = tokio::spawn(run_discovery(
+            client,
+            refresh_interval,
+            topology_tx,
+            events.clone(),
+        ));
+
+        Self {
+            topology,
+            events,
+            handle,
+        }
+    }
+
+    // Receiver of the latest topology snapshot
+    pub fn subscribe_topology(&self) -> watch::Receiver<Arc<Topology>> {
+        self.topology.clone()
+    }
+
+    // Receiver of the events of every refresh from now on
+    pub fn subscribe_events(&self) -> broadcast::Receiver<TopologyEvent> {
+        self.events.subscribe()
+    }
+
+    // Wait for the task to stop, which only happens on a fatal error
+    pub async fn join(self) -> Result<(), anyhow::Error> {
+        Ok(self.handle.await??)
+    }
+
+    pub fn abort(&self) {
+        self.handle.abort();
+    }
+}
+
+async fn run_discovery(
+    client: ZeaburClient,
+    refresh_interval: Duration,
+    topology_tx: watch::Sender<Arc<Topology>>,
+    events: broadcast::Sender<TopologyEvent>,
+) -> Result<(), ZeaburError> {
+    let mut interval = tokio::time::interval(refresh_interval);
+
+    loop {
+        interval.tick().await;
+
+        let mut topology = match client.discover_topology().await {
+            Ok(topology) => topology,
+            // Refreshing again cannot fix a rejected API key
+            Err(ZeaburError::Unauthorized) => return Err(ZeaburError::Unauthorized),
+            Err(e) => {
+                log::warn!(
+                    "Failed to refresh topology, keeping the previous one: {}",
+                    e
+                );
+                continue;
+            }
+        };
+
+        let previous = topology_tx.borrow().clone();
+        carry_over_failed_projects(&previous, &mut topology);
+
+        let changes = diff_topology(&previous, &topology);
+        log::info!(
+            "Discovered {} services, {} changes",
+            topology.services.len(),
+            changes.len()
+        );
+
+        topology_tx.send_replace(Arc::new(topology));
+        for event in changes {
+            // Nobody listening for events is fine
+            let _ = events.send(event);
+        }
+    }
+}
+
+// Topology of every account, by account name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccountTopologies {
+    pub accounts: BTreeMap<String, Arc<Topology>>,
+    // Accounts whose discovery stopped on a fatal error such as a rejected
+    // API key, with the reason. The other accounts are not affected.
+    pub failed: BTreeMap<String, String>,
+}
+
+// One discovery task per account, merged into a single snapshot
+pub struct AccountDiscovery {
+    topologies: watch::Receiver<Arc<AccountTopologies>>,
+    shutdown: CancellationToken,
+}
+
+impl AccountDiscovery {
+    // The snapshot starts with the topologies of `initial` which belong to one
+    // of the accounts, e.g. the ones known before discovery was restarted, until
+    // these accounts are discovered again
+    pub fn spawn(
+        clients: BTreeMap<String, ZeaburClient>,
+        refresh_interval: Duration,
+        mut initial: AccountTopologies,
+    ) -> Self {
+        initial.accounts.retain(|name, _| clients.contains_key(name));
+        initial.failed.clear();
+        let (topologies_tx, topologies) = watch::channel(Arc::new(initial));
+        let shutdown = CancellationToken::new();
+
+        for (name, client) in clients {
+            tokio::spawn(follow_account(
+                name,
+                DiscoveryTask::spawn(client, refresh_interval),
+                topologies_tx.clone(),
+                shutdown.clone(),
+            ));
+        }
+
+        Self {
+            topologies,
+            shutdown,
+        }
+    }
+
+    // Receiver of the latest snapshot, closed once every account has stopped
+    pub fn subscribe_topologies(&self) -> watch::Receiver<Arc<AccountTopologies>> {
+        self.topologies.clone()
+    }
+
+    pub fn abort(&self) {
+        self.shutdown.cancel();
+    }
+}
+
+impl Drop for AccountDiscovery {
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+// Copy every topology of an account into the merged snapshot, and drop the
+// account from it once its discovery stops
+async fn follow_account(
+    name: String,
+    task: DiscoveryTask,
+    topologies_tx: watch::Sender<Arc<AccountTopologies>>,
+    shutdown: CancellationToken,
+) {
+    let mut topology = task.subscribe_topology();
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => {
+                task.abort();
+                return;
+            }
+            changed = topology.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                let snapshot = topology.borrow_and_update().clone();
+                topologies_tx.send_modify(|all| {
+                    Arc::make_mut(all).accounts.insert(name.clone(), snapshot);
+                });
+            }
+        }
+    }
+
+    let reason = match task.join().await {
+        Ok(()) => "discovery stopped".to_string(),
+        Err(e) => e.to_string(),
+    };
+    log::error!("Discovery of account {} stopped: {}", name, reason);
+    topologies_tx.send_modify(|all| {
+        let all = Arc::make_mut(all);
+        all.accounts.remove(&name);
+        all.failed.insert(name, reason);
+    });
+}
@@ -0,0 +1,5 @@
+pub mod adaptive_interval;
+pub mod collection;
+pub mod discovery;
+pub mod reload;
+pub mod supervisor;
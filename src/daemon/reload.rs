@@ -0,0 +1,96 @@
+use anyhow::Error;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+// Notifies when the config should be reloaded, on SIGHUP or when the config
+// file changes on disk
+pub struct ReloadSignals {
+    triggers: mpsc::UnboundedReceiver<()>,
+    debounce: Duration,
+    // Dropping the watcher stops watching the file
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl ReloadSignals {
+    // Editors usually write a burst of events for a single save
+    pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+    pub fn new(config_path: Option<&Path>) -> Result<Self, Error> {
+        let (sender, triggers) = mpsc::unbounded_channel();
+
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+
+            let mut hangups = signal(SignalKind::hangup())?;
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                while hangups.recv().await.is_some() {
+                    log::info!("Received SIGHUP, reloading the config");
+                    if sender.send(()).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+
+        let watcher = match config_path {
+            Some(path) => Some(watch_file(path, sender)?),
+            None => None,
+        };
+
+        Ok(Self {
+            triggers,
+            debounce: Self::DEFAULT_DEBOUNCE,
+            _watcher: watcher,
+        })
+    }
+
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    // Wait for the next reload, folding triggers which arrive within the
+    // debounce window into one
+    pub async fn recv(&mut self) -> Option<()> {
+        self.triggers.recv().await?;
+        tokio::time::sleep(self.debounce).await;
+        while self.triggers.try_recv().is_ok() {}
+        Some(())
+    }
+}
+
+// Watch the directory of the file rather than the file itself, so that
+// replacing the file (as editors and Kubernetes config maps do) is noticed
+fn watch_file(path: &Path, sender: mpsc::UnboundedSender<()>) -> Result<RecommendedWatcher, Error> {
+    let path = std::path::absolute(path)?;
+    let directory = path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let file_name = path.file_name().map(|name| name.to_os_string());
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if event.kind.is_access() {
+            return;
+        }
+        // Config maps swap a `..data` symlink instead of touching the file
+        let relevant = event.paths.iter().any(|changed| {
+            let name = changed.file_name();
+            name == file_name.as_deref()
+                || name.is_some_and(|name| name.to_string_lossy().starts_with(".."))
+        });
+        if relevant {
+            let _ = sender.send(());
+        }
+    })?;
+    watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+
+    Ok(watcher)
+}
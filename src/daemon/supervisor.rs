@@ -0,0 +1,255 @@
+use anyhow::Error;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::{AbortHandle, JoinHandle};
+use tokio_util::sync::CancellationToken;
+
+use super::adaptive_interval::AdaptiveInterval;
+use crate::log::{log_collector::LogCollector, log_sink::LogSink};
+use crate::zeabur::service_key::ServiceKey;
+
+// How a single service is polled
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollSettings {
+    // Bounds of the interval, which adapts to the log volume of the service
+    pub min_interval: Duration,
+    pub max_interval: Duration,
+    // Upper bound of one collect, store and commit round
+    pub timeout: Duration,
+    // Pause before a panicked poller is started again
+    pub restart_delay: Duration,
+}
+
+impl Default for PollSettings {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(2),
+            max_interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(30),
+            restart_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+// Collect logs, store them and move the cursor once the sink confirmed them
+pub async fn poll_once(collector: &dyn LogCollector, sink: &dyn LogSink) -> Result<usize, Error> {
+    let logs = collector.collect_logs().await?;
+    let log_count = logs.len();
+
+    sink.store_logs(logs).await?;
+
+    // Move the cursor only after the sink confirmed the batch
+    collector.commit(sink.held_back_since()).await?;
+
+    Ok(log_count)
+}
+
+// Runs every service in its own task, so a slow or crashing service does not
+// hold back the others. Polls across all services share a concurrency limit.
+pub struct Supervisor {
+    permits: Arc<Semaphore>,
+    // Cancelled on shutdown, pollers then stop once their current poll is done
+    shutdown: CancellationToken,
+    tasks: HashMap<ServiceKey, JoinHandle<()>>,
+}
+
+// Outcome of a graceful shutdown
+#[derive(Debug, Default, PartialEq)]
+pub struct ShutdownReport {
+    // Pollers which finished their last poll in time
+    pub stopped: Vec<ServiceKey>,
+    // Pollers still busy at the deadline, their last batch may be sent again
+    pub aborted: Vec<ServiceKey>,
+    // Sinks which failed to flush
+    pub failed: Vec<(ServiceKey, String)>,
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.aborted.is_empty() && self.failed.is_empty()
+    }
+}
+
+impl Supervisor {
+    pub fn new(max_concurrent_polls: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_concurrent_polls.max(1))),
+            shutdown: CancellationToken::new(),
+            tasks: HashMap::new(),
+        }
+    }
+
+    // Start polling a service, replacing the task already running for it
+    pub fn start(
+        &mut self,
+        key: ServiceKey,
+        collector: Arc<dyn LogCollector>,
+        sink: Arc<dyn LogSink>,
+        settings: PollSettings,
+    ) {
+        let handle = tokio::spawn(supervise(
+            key.clone(),
+            collector,
+            sink,
+            settings,
+            self.permits.clone(),
+            self.shutdown.clone(),
+        ));
+        if let Some(previous) = self.tasks.insert(key, handle) {
+            previous.abort();
+        }
+    }
+
+    // Stop polling a service, returning whether it was running
+    pub fn stop(&mut self, key: &ServiceKey) -> bool {
+        match self.tasks.remove(key) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        for (_, handle) in self.tasks.drain() {
+            handle.abort();
+        }
+    }
+
+    // Let every poller finish its current poll, so the batch it is storing is
+    // committed, and abort the ones still busy at the deadline
+    pub async fn shutdown(&mut self, deadline: Duration) -> ShutdownReport {
+        self.shutdown.cancel();
+        let deadline = tokio::time::Instant::now() + deadline;
+        let mut report = ShutdownReport::default();
+
+        let mut tasks: Vec<(ServiceKey, JoinHandle<()>)> = self.tasks.drain().collect();
+        tasks.sort_by(|a, b| a.0.cmp(&b.0));
+        for (key, mut handle) in tasks {
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(_) => report.stopped.push(key),
+                Err(_) => {
+                    handle.abort();
+                    report.aborted.push(key);
+                }
+            }
+        }
+
+        report
+    }
+
+    pub fn is_running(&self, key: &ServiceKey) -> bool {
+        self.tasks.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+impl Drop for Supervisor {
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+// Aborts the poller when its supervising task is aborted
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+// Restart the poller of a service whenever it panics
+async fn supervise(
+    key: ServiceKey,
+    collector: Arc<dyn LogCollector>,
+    sink: Arc<dyn LogSink>,
+    settings: PollSettings,
+    permits: Arc<Semaphore>,
+    shutdown: CancellationToken,
+) {
+    loop {
+        let mut poller = tokio::spawn(poll_loop(
+            key.clone(),
+            collector.clone(),
+            sink.clone(),
+            settings.clone(),
+            permits.clone(),
+            shutdown.clone(),
+        ));
+        let _guard = AbortOnDrop(poller.abort_handle());
+
+        match (&mut poller).await {
+            Err(e) if e.is_panic() => {
+                log::error!(
+                    "Poller for {} panicked, restarting in {:?}",
+                    key,
+                    settings.restart_delay
+                );
+                tokio::time::sleep(settings.restart_delay).await;
+            }
+            _ => return,
+        }
+        if shutdown.is_cancelled() {
+            return;
+        }
+    }
+}
+
+async fn poll_loop(
+    key: ServiceKey,
+    collector: Arc<dyn LogCollector>,
+    sink: Arc<dyn LogSink>,
+    settings: PollSettings,
+    permits: Arc<Semaphore>,
+    shutdown: CancellationToken,
+) {
+    let mut interval = AdaptiveInterval::new(settings.min_interval, settings.max_interval);
+
+    loop {
+        // No new poll is started once shutting down, a poll in progress is
+        // finished so its batch gets stored and committed
+        let permit = tokio::select! {
+            _ = shutdown.cancelled() => return,
+            permit = permits.acquire() => permit,
+        };
+        // The semaphore is never closed
+        let Ok(permit) = permit else {
+            return;
+        };
+
+        match tokio::time::timeout(settings.timeout, poll_once(&*collector, &*sink)).await {
+            Ok(Ok(log_count)) => {
+                let next = interval.observe(collector.poll_load().await);
+                log::info!(
+                    "Processed {} logs for {}, next poll in {:?}",
+                    log_count,
+                    key,
+                    next
+                );
+            }
+            Ok(Err(e)) => log::warn!("Error processing logs for {}: {}", key, e),
+            Err(_) => log::warn!(
+                "Processing logs for {} timed out after {:?}",
+                key,
+                settings.timeout
+            ),
+        }
+        drop(permit);
+
+        tokio::select! {
+            _ = shutdown.cancelled() => return,
+            _ = tokio::time::sleep(interval.current()) => {}
+        }
+    }
+}
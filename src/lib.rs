@@ -1,2 +1,4 @@
+pub mod config;
+pub mod daemon;
 pub mod log;
 pub mod zeabur;
@@ -0,0 +1,100 @@
+use crate::log::checkpoint_store::{Checkpoint, CheckpointStore};
+use crate::zeabur::service_key::ServiceKey;
+use anyhow::{Context, Error};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tokio::sync::Mutex;
+
+// On-disk representation of a single checkpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CheckpointRecord {
+    project_id: String,
+    environment_id: String,
+    service_id: String,
+    #[serde(flatten)]
+    checkpoint: Checkpoint,
+}
+
+// Stores all checkpoints in a single JSON file, rewritten atomically on commit
+pub struct FileCheckpointStore {
+    path: PathBuf,
+    checkpoints: Mutex<BTreeMap<ServiceKey, Checkpoint>>,
+}
+
+impl FileCheckpointStore {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+
+        let checkpoints = match tokio::fs::read(&path).await {
+            Ok(content) => {
+                let records: Vec<CheckpointRecord> = serde_json::from_slice(&content)
+                    .with_context(|| {
+                        format!("Failed to parse checkpoint file {}", path.display())
+                    })?;
+                records
+                    .into_iter()
+                    .map(|record| {
+                        (
+                            ServiceKey::new(
+                                record.project_id,
+                                record.environment_id,
+                                record.service_id,
+                            ),
+                            record.checkpoint,
+                        )
+                    })
+                    .collect()
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(Error::new(e)
+                    .context(format!("Failed to read checkpoint file {}", path.display())))
+            }
+        };
+
+        Ok(Self {
+            path,
+            checkpoints: Mutex::new(checkpoints),
+        })
+    }
+
+    async fn persist(&self, checkpoints: &BTreeMap<ServiceKey, Checkpoint>) -> Result<(), Error> {
+        let records: Vec<CheckpointRecord> = checkpoints
+            .iter()
+            .map(|(key, checkpoint)| CheckpointRecord {
+                project_id: key.project_id.clone(),
+                environment_id: key.environment_id.clone(),
+                service_id: key.service_id.clone(),
+                checkpoint: checkpoint.clone(),
+            })
+            .collect();
+        let content = serde_json::to_vec_pretty(&records)?;
+
+        // Write to a sibling file first so a crash never leaves a torn file behind
+        let tmp_path = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, content)
+            .await
+            .with_context(|| format!("Failed to write checkpoint file {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, &self.path)
+            .await
+            .with_context(|| {
+                format!("Failed to replace checkpoint file {}", self.path.display())
+            })?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn load(&self, key: &ServiceKey) -> Result<Option<Checkpoint>, Error> {
+        Ok(self.checkpoints.lock().await.get(key).cloned())
+    }
+
+    async fn commit(&self, key: &ServiceKey, checkpoint: &Checkpoint) -> Result<(), Error> {
+        let mut checkpoints = self.checkpoints.lock().await;
+        checkpoints.insert(key.clone(), checkpoint.clone());
+        self.persist(&checkpoints).await
+    }
+}
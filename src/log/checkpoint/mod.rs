@@ -0,0 +1,20 @@
+pub mod file_checkpoint_store;
+pub mod sqlite_checkpoint_store;
+
+use anyhow::Error;
+use std::sync::Arc;
+
+use super::checkpoint_store::CheckpointStore;
+use file_checkpoint_store::FileCheckpointStore;
+use sqlite_checkpoint_store::SqliteCheckpointStore;
+
+// Open a checkpoint store from a spec such as `sqlite:/data/cursors.db` or
+// `file:/data/cursors.json`. A bare path is treated as a JSON file.
+pub async fn open_checkpoint_store(spec: &str) -> Result<Arc<dyn CheckpointStore>, Error> {
+    if let Some(path) = spec.strip_prefix("sqlite:") {
+        Ok(Arc::new(SqliteCheckpointStore::open(path).await?))
+    } else {
+        let path = spec.strip_prefix("file:").unwrap_or(spec);
+        Ok(Arc::new(FileCheckpointStore::open(path).await?))
+    }
+}
@@ -0,0 +1,103 @@
+use crate::log::checkpoint_store::{Checkpoint, CheckpointStore};
+use crate::zeabur::service_key::ServiceKey;
+use anyhow::{Context, Error};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+// Stores checkpoints in a SQLite database, one row per service
+pub struct SqliteCheckpointStore {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteCheckpointStore {
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        let connection = tokio::task::spawn_blocking(move || -> Result<Connection, Error> {
+            let connection = Connection::open(&path).with_context(|| {
+                format!("Failed to open checkpoint database {}", path.display())
+            })?;
+            connection.execute_batch(
+                "CREATE TABLE IF NOT EXISTS checkpoints (
+                    project_id TEXT NOT NULL,
+                    environment_id TEXT NOT NULL,
+                    service_id TEXT NOT NULL,
+                    timestamp TEXT NOT NULL,
+                    PRIMARY KEY (project_id, environment_id, service_id)
+                )",
+            )?;
+            Ok(connection)
+        })
+        .await??;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    // Run a closure against the connection on the blocking thread pool
+    async fn with_connection<T, F>(&self, f: F) -> Result<T, Error>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, Error> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || {
+            let connection = connection
+                .lock()
+                .map_err(|_| anyhow::anyhow!("Checkpoint database lock poisoned"))?;
+            f(&connection)
+        })
+        .await?
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for SqliteCheckpointStore {
+    async fn load(&self, key: &ServiceKey) -> Result<Option<Checkpoint>, Error> {
+        let key = key.clone();
+        self.with_connection(move |connection| {
+            let timestamp: Option<String> = connection
+                .query_row(
+                    "SELECT timestamp FROM checkpoints
+                     WHERE project_id = ?1 AND environment_id = ?2 AND service_id = ?3",
+                    params![key.project_id, key.environment_id, key.service_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            timestamp
+                .map(|ts| {
+                    let timestamp = DateTime::parse_from_rfc3339(&ts)
+                        .with_context(|| format!("Invalid checkpoint timestamp {}", ts))?
+                        .with_timezone(&Utc);
+                    Ok(Checkpoint { timestamp })
+                })
+                .transpose()
+        })
+        .await
+    }
+
+    async fn commit(&self, key: &ServiceKey, checkpoint: &Checkpoint) -> Result<(), Error> {
+        let key = key.clone();
+        let timestamp = checkpoint.timestamp.to_rfc3339();
+        self.with_connection(move |connection| {
+            connection.execute(
+                "INSERT INTO checkpoints (project_id, environment_id, service_id, timestamp)
+                 VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (project_id, environment_id, service_id)
+                 DO UPDATE SET timestamp = excluded.timestamp",
+                params![
+                    key.project_id,
+                    key.environment_id,
+                    key.service_id,
+                    timestamp
+                ],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+}
@@ -0,0 +1,22 @@
+use anyhow::Error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::zeabur::service_key::ServiceKey;
+
+// Position of a collector in the log stream of a single service
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub timestamp: DateTime<Utc>,
+}
+
+// Define the CheckpointStore trait
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    // Load the last committed checkpoint of a service, if there is one
+    async fn load(&self, key: &ServiceKey) -> Result<Option<Checkpoint>, Error>;
+
+    // Durably record that everything up to the checkpoint has been delivered
+    async fn commit(&self, key: &ServiceKey, checkpoint: &Checkpoint) -> Result<(), Error>;
+}
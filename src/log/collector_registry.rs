@@ -0,0 +1,173 @@
+use anyhow::Error;
+use std::collections::HashMap;
+
+use crate::zeabur::service_key::ServiceKey;
+
+// A service discovered on Zeabur that should have a collector and a sink
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceTarget {
+    pub key: ServiceKey,
+    pub labels: HashMap<String, String>,
+}
+
+// A collector and sink pair owned by the registry
+pub struct RegisteredCollector<C, S> {
+    pub target: ServiceTarget,
+    pub collector: C,
+    pub sink: S,
+}
+
+// Changes applied to the registry by a single call to sync
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncReport {
+    pub added: Vec<ServiceKey>,
+    pub updated: Vec<ServiceKey>,
+    pub retired: Vec<ServiceKey>,
+    pub failed: Vec<(ServiceKey, String)>,
+}
+
+// Keeps collectors and sinks alive across polling ticks, so that collector
+// state such as the last seen timestamp is not reset on every tick
+pub struct CollectorRegistry<C, S> {
+    entries: HashMap<ServiceKey, RegisteredCollector<C, S>>,
+}
+
+impl<C, S> Default for CollectorRegistry<C, S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C, S> CollectorRegistry<C, S> {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+
+    // Reconcile the registry with the latest discovery result.
+    //
+    // New targets get a collector and a sink from the given factories, targets
+    // which disappeared are retired, and targets whose labels changed get a new
+    // sink while keeping their collector (and its cursor). A target whose
+    // collector or sink cannot be built is reported as failed and skipped, so
+    // it is retried on the next sync.
+    pub fn sync<FC, FS>(
+        &mut self,
+        targets: Vec<ServiceTarget>,
+        mut make_collector: FC,
+        mut make_sink: FS,
+    ) -> SyncReport
+    where
+        FC: FnMut(&ServiceTarget) -> Result<C, Error>,
+        FS: FnMut(&ServiceTarget) -> Result<S, Error>,
+    {
+        let mut report = SyncReport::default();
+        let mut desired: HashMap<ServiceKey, ServiceTarget> = targets
+            .into_iter()
+            .map(|target| (target.key.clone(), target))
+            .collect();
+
+        // Retire collectors whose service is gone
+        let retired: Vec<ServiceKey> = self
+            .entries
+            .keys()
+            .filter(|key| !desired.contains_key(*key))
+            .cloned()
+            .collect();
+        for key in retired {
+            self.entries.remove(&key);
+            report.retired.push(key);
+        }
+
+        for (key, target) in desired.drain() {
+            match self.entries.get_mut(&key) {
+                Some(entry) => {
+                    if entry.target.labels != target.labels {
+                        match make_sink(&target) {
+                            Ok(sink) => {
+                                entry.sink = sink;
+                                entry.target = target;
+                                report.updated.push(key);
+                            }
+                            Err(e) => report.failed.push((key, e.to_string())),
+                        }
+                    }
+                }
+                None => {
+                    let built = make_collector(&target)
+                        .and_then(|collector| Ok((collector, make_sink(&target)?)));
+                    let (collector, sink) = match built {
+                        Ok(pair) => pair,
+                        Err(e) => {
+                            report.failed.push((key, e.to_string()));
+                            continue;
+                        }
+                    };
+                    self.entries.insert(
+                        key.clone(),
+                        RegisteredCollector {
+                            target,
+                            collector,
+                            sink,
+                        },
+                    );
+                    report.added.push(key);
+                }
+            }
+        }
+
+        report.added.sort();
+        report.updated.sort();
+        report.retired.sort();
+        report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+        report
+    }
+
+    // Replace the sinks of the given entries while keeping their collectors,
+    // for when something other than the labels changed how sinks are built
+    pub fn rebuild_sinks<FS>(&mut self, keys: &[ServiceKey], mut make_sink: FS) -> SyncReport
+    where
+        FS: FnMut(&ServiceTarget) -> Result<S, Error>,
+    {
+        let mut report = SyncReport::default();
+
+        for key in keys {
+            let Some(entry) = self.entries.get_mut(key) else {
+                continue;
+            };
+            match make_sink(&entry.target) {
+                Ok(sink) => {
+                    entry.sink = sink;
+                    report.updated.push(key.clone());
+                }
+                Err(e) => report.failed.push((key.clone(), e.to_string())),
+            }
+        }
+
+        report.updated.sort();
+        report.failed.sort_by(|a, b| a.0.cmp(&b.0));
+        report
+    }
+
+    // Drop an entry, so the next sync builds a new collector for it
+    pub fn remove(&mut self, key: &ServiceKey) -> Option<RegisteredCollector<C, S>> {
+        self.entries.remove(key)
+    }
+
+    pub fn get(&self, key: &ServiceKey) -> Option<&RegisteredCollector<C, S>> {
+        self.entries.get(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredCollector<C, S>> {
+        self.entries.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+
+// Identity of a single runtime log line
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DedupKey {
+    pub timestamp: DateTime<Utc>,
+    pub zeabur_uid: String,
+    pub message_hash: u64,
+}
+
+impl DedupKey {
+    pub fn new(timestamp: DateTime<Utc>, zeabur_uid: &str, message: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        message.hash(&mut hasher);
+        Self {
+            timestamp,
+            zeabur_uid: zeabur_uid.to_string(),
+            message_hash: hasher.finish(),
+        }
+    }
+}
+
+// Remembers the most recently shipped log lines, evicting the oldest ones
+// once the capacity is reached
+pub struct DedupWindow {
+    capacity: usize,
+    order: VecDeque<DedupKey>,
+    seen: HashSet<DedupKey>,
+}
+
+impl DedupWindow {
+    pub const DEFAULT_CAPACITY: usize = 4096;
+
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            order: VecDeque::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    pub fn contains(&self, key: &DedupKey) -> bool {
+        self.seen.contains(key)
+    }
+
+    // Record a key, returning false if it was already in the window
+    pub fn insert(&mut self, key: DedupKey) -> bool {
+        if !self.seen.insert(key.clone()) {
+            return false;
+        }
+        self.order.push_back(key);
+
+        while self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.seen.remove(&evicted);
+            }
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+impl Default for DedupWindow {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
@@ -1,8 +1,34 @@
+use super::log_entry::LogEntry;
 use anyhow::Error;
 use async_trait::async_trait;
-use super::log_entry::LogEntry;
+use chrono::{DateTime, Utc};
+
+// How busy a service looked on the last poll, used to adapt its poll interval
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PollLoad {
+    // Nothing new was returned
+    Idle,
+    #[default]
+    Steady,
+    // Logs may have been missed since the previous poll, e.g. a full page or
+    // a page which does not reach back to the previous one
+    Saturated,
+}
 
 #[async_trait]
-pub trait LogCollector {
+pub trait LogCollector: Send + Sync {
     async fn collect_logs(&self) -> Result<Vec<LogEntry>, Error>;
+
+    // Called once the logs returned by the last collect_logs call have been
+    // stored by the sink, so the collector can move its cursor past them. Lines
+    // from `held_back` on are still held back by the sink and have to be read
+    // again after a restart.
+    async fn commit(&self, _held_back: Option<DateTime<Utc>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    // Load observed by the last collect_logs call
+    async fn poll_load(&self) -> PollLoad {
+        PollLoad::Steady
+    }
 }
@@ -1,9 +1,176 @@
-use std::collections::HashMap;
-
 use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::zeabur::topology::DiscoveredService;
+
+// Severity of a log line, in the ranges of the OpenTelemetry log data model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogSeverity {
+    // Not known, e.g. the line has not been classified
+    #[default]
+    Unspecified,
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+// Structured value of a body or an attribute, mirroring the OpenTelemetry AnyValue
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogValue {
+    String(String),
+    Bool(bool),
+    Int(i64),
+    Double(f64),
+    Bytes(Vec<u8>),
+    Array(Vec<LogValue>),
+    Map(BTreeMap<String, LogValue>),
+}
+
+impl LogValue {
+    // Text of a scalar value, as matched by patterns
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            LogValue::String(s) => Some(s.clone()),
+            LogValue::Bool(b) => Some(b.to_string()),
+            LogValue::Int(i) => Some(i.to_string()),
+            LogValue::Double(d) => Some(d.to_string()),
+            LogValue::Bytes(_) | LogValue::Array(_) | LogValue::Map(_) => None,
+        }
+    }
+}
+
+impl From<&str> for LogValue {
+    fn from(value: &str) -> Self {
+        LogValue::String(value.to_string())
+    }
+}
+
+impl From<String> for LogValue {
+    fn from(value: String) -> Self {
+        LogValue::String(value)
+    }
+}
+
+impl From<i64> for LogValue {
+    fn from(value: i64) -> Self {
+        LogValue::Int(value)
+    }
+}
 
-// Define the LogEntry struct with public fields
+impl From<bool> for LogValue {
+    fn from(value: bool) -> Self {
+        LogValue::Bool(value)
+    }
+}
+
+// Where a log line comes from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogSource {
+    pub account: String,
+    pub project_id: String,
+    pub project_name: String,
+    pub environment_id: String,
+    pub environment_name: String,
+    pub service_id: String,
+    pub service_name: String,
+    // Provider, name and ID of the region, e.g. aws-Tokyo-aws-tokyo-1
+    pub region: String,
+    pub deployment_id: Option<String>,
+    // zeaburUID of the instance which wrote the line
+    pub instance: Option<String>,
+}
+
+impl LogSource {
+    // Source of the lines of a service discovered with the given account
+    pub fn new(account: &str, service: &DiscoveredService) -> Self {
+        Self {
+            account: account.to_string(),
+            project_id: service.key.project_id.clone(),
+            project_name: service.project_name.clone(),
+            environment_id: service.key.environment_id.clone(),
+            environment_name: service.environment_name.clone(),
+            service_id: service.key.service_id.clone(),
+            service_name: service.service_name.clone(),
+            region: format!(
+                "{}-{}-{}",
+                service.region.provider, service.region.name, service.region.id
+            ),
+            deployment_id: service.latest_deployment.id.clone(),
+            instance: None,
+        }
+    }
+
+    // Labels identifying the service, shared by all of its lines. The
+    // deployment and instance vary from line to line and are left out.
+    pub fn labels(&self) -> HashMap<String, String> {
+        let mut labels = HashMap::new();
+
+        // loki has its taste on indexing labels: https://grafana.com/docs/loki/latest/send-data/otel/#format-considerations
+        labels.insert("service.name".to_string(), self.service_name.clone());
+        labels.insert("service.namespace".to_string(), self.project_name.clone());
+        labels.insert("cloud.region".to_string(), self.region.clone());
+
+        labels.insert("project_name".to_string(), self.project_name.clone());
+        labels.insert("service_name".to_string(), self.service_name.clone());
+        labels.insert(
+            "environment_name".to_string(),
+            self.environment_name.clone(),
+        );
+        labels.insert("project_id".to_string(), self.project_id.clone());
+        labels.insert("service_id".to_string(), self.service_id.clone());
+        labels.insert("environment_id".to_string(), self.environment_id.clone());
+        labels.insert("account".to_string(), self.account.clone());
+
+        labels
+    }
+}
+
+// A single log line and what is known about it
+#[derive(Debug, Clone)]
 pub struct LogEntry {
     pub timestamp: DateTime<Utc>,
+    // When zeabur-ops read the line
+    pub observed_timestamp: DateTime<Utc>,
+    pub severity: LogSeverity,
+    // The line as written by the service
     pub message: String,
+    // Structured body exported instead of the message, e.g. a parsed JSON line
+    pub body: Option<LogValue>,
+    pub attributes: BTreeMap<String, LogValue>,
+    // Trace context as hex IDs, when the line names the trace it belongs to
+    pub trace_id: Option<String>,
+    pub span_id: Option<String>,
+    pub source: LogSource,
+}
+
+impl LogEntry {
+    // A line of unknown severity and source, observed now
+    pub fn new(timestamp: DateTime<Utc>, message: impl Into<String>) -> Self {
+        Self {
+            timestamp,
+            observed_timestamp: Utc::now(),
+            severity: LogSeverity::Unspecified,
+            message: message.into(),
+            body: None,
+            attributes: BTreeMap::new(),
+            trace_id: None,
+            span_id: None,
+            source: LogSource::default(),
+        }
+    }
+
+    pub fn with_source(mut self, source: LogSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    // The body to export: the structured one when set, the message otherwise
+    pub fn body(&self) -> LogValue {
+        self.body
+            .clone()
+            .unwrap_or_else(|| LogValue::String(self.message.clone()))
+    }
 }
@@ -0,0 +1,28 @@
+use std::collections::BTreeMap;
+
+use super::log_entry::LogEntry;
+
+// A batch on its way from the collector to the sinks
+#[derive(Debug, Clone, Default)]
+pub struct ProcessedLogs {
+    // Entries for the sink of the pipeline
+    pub logs: Vec<LogEntry>,
+    // Entries sent to other sinks, by sink name
+    pub routed: BTreeMap<String, Vec<LogEntry>>,
+}
+
+impl ProcessedLogs {
+    pub fn new(logs: Vec<LogEntry>) -> Self {
+        Self {
+            logs,
+            routed: BTreeMap::new(),
+        }
+    }
+}
+
+// A step between the collector and the sink of a service, run on every batch
+// before it is stored. Processors are chained, each one sees what the
+// previous ones left in the batch.
+pub trait LogProcessor: Send + Sync {
+    fn process(&self, batch: &mut ProcessedLogs);
+}
@@ -1,12 +1,24 @@
 // Import necessary traits and types
 use anyhow::Error;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use super::log_entry::LogEntry;
 
 // Define the LogSink trait
 #[async_trait]
-pub trait LogSink {
+pub trait LogSink: Send + Sync {
     // Method to store logs
     async fn store_logs(&self, logs: Vec<LogEntry>) -> Result<(), Error>;
+
+    // Called on shutdown to push out anything the sink still buffers
+    async fn flush(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    // Timestamp of the oldest line the sink took but holds back for now, the
+    // checkpoint of the collector must not move past it
+    fn held_back_since(&self) -> Option<DateTime<Utc>> {
+        None
+    }
 }
@@ -1,5 +1,14 @@
+pub mod checkpoint;
+pub mod checkpoint_store;
+pub mod collector_registry;
+pub mod dedup_window;
 pub mod log_collector;
 pub mod log_entry;
+pub mod log_processor;
 pub mod log_sink;
+pub mod multiline;
+pub mod parse;
+pub mod processor;
+pub mod severity;
 pub mod sink;
 pub mod zeabur_log_collector;
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::log_entry::LogEntry;
+use super::severity::LinePattern;
+
+// Continuation rules of common stack traces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MultilinePreset {
+    Java,
+    Python,
+    Go,
+    Node,
+}
+
+impl MultilinePreset {
+    pub fn continuation(&self) -> LinePattern {
+        let pattern = match self {
+            // Frames, "... 12 more" and chained causes
+            Self::Java => {
+                r"^(\s+(at |\.\.\. \d+ (more|common frames omitted))|\s*(Caused by|Suppressed): )"
+            }
+            // The traceback joins the line logging it, down to the exception
+            // line, along with chained tracebacks
+            Self::Python => {
+                r"^(\s+|$|Traceback \(most recent call last\):|During handling of the above exception|The above exception was the direct cause|[\w.]*(Error|Exception|Exit|Interrupt|Warning)(: .*)?$)"
+            }
+            // Goroutine dumps of a panic
+            Self::Go => {
+                r"^(\s|$|goroutine \d+ \[|\[signal |exit status \d+|created by |[\w./*()\[\]-]+\(.*\)$)"
+            }
+            Self::Node => r"^\s+(at |\.\.\. \d+ more)",
+        };
+        LinePattern::try_from(pattern.to_string()).expect("preset patterns are valid")
+    }
+}
+
+// How the lines of a service are combined. A line matching `start` begins a
+// new entry and a line matching `continuation` joins the previous one. With
+// only `start`, every other line is a continuation, with only `continuation`
+// every other line begins a new entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultilineRule {
+    pub start: Option<LinePattern>,
+    pub continuation: Option<LinePattern>,
+    // Bounds of a combined entry, the line going past them begins a new one
+    pub max_lines: usize,
+    pub max_bytes: usize,
+    // How long an entry may wait for more lines, checked on every poll
+    pub flush_timeout: Duration,
+}
+
+impl Default for MultilineRule {
+    fn default() -> Self {
+        Self {
+            start: None,
+            continuation: None,
+            max_lines: 500,
+            max_bytes: 64 * 1024,
+            flush_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl MultilineRule {
+    pub fn joins(&self, line: &str) -> bool {
+        let starts = self.start.as_ref().map(|start| start.is_match(line));
+        let continues = self
+            .continuation
+            .as_ref()
+            .map(|continuation| continuation.is_match(line));
+        match (starts, continues) {
+            (Some(starts), None) => !starts,
+            (None, Some(continues)) => continues,
+            (Some(starts), Some(continues)) => continues && !starts,
+            (None, None) => false,
+        }
+    }
+}
+
+// An entry still taking lines
+#[derive(Debug, Clone)]
+struct Group {
+    entry: LogEntry,
+    lines: usize,
+    updated: Instant,
+}
+
+// Combines consecutive lines of the same instance into one entry. The last
+// entry of every instance is held back until a line begins the next one, it
+// goes past the bounds, or it waited for the flush timeout.
+#[derive(Debug, Clone)]
+pub struct MultilineCombiner {
+    rule: MultilineRule,
+    // Keyed by instance, so the lines of replicas logging at the same time
+    // do not break each other's entries
+    pending: BTreeMap<Option<String>, Group>,
+}
+
+impl MultilineCombiner {
+    pub fn new(rule: MultilineRule) -> Self {
+        Self {
+            rule,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    // Add a batch of lines, returning the entries which are complete
+    pub fn push(&mut self, logs: Vec<LogEntry>, now: Instant) -> Vec<LogEntry> {
+        let mut complete = Vec::new();
+        for entry in logs {
+            let instance = entry.source.instance.clone();
+            if let Some(group) = self.pending.get_mut(&instance) {
+                let fits = group.lines < self.rule.max_lines
+                    && group.entry.message.len() + 1 + entry.message.len() <= self.rule.max_bytes;
+                if fits && self.rule.joins(&entry.message) {
+                    group.entry.message.push('\n');
+                    group.entry.message.push_str(&entry.message);
+                    group.lines += 1;
+                    group.updated = now;
+                    continue;
+                }
+            }
+
+            let group = Group {
+                entry,
+                lines: 1,
+                updated: now,
+            };
+            complete.extend(
+                self.pending
+                    .insert(instance, group)
+                    .map(|group| group.entry),
+            );
+        }
+
+        let timed_out: Vec<Option<String>> = self
+            .pending
+            .iter()
+            .filter(|(_, group)| now.duration_since(group.updated) >= self.rule.flush_timeout)
+            .map(|(instance, _)| instance.clone())
+            .collect();
+        for instance in timed_out {
+            complete.extend(self.pending.remove(&instance).map(|group| group.entry));
+        }
+        complete
+    }
+
+    // Give up waiting for more lines of the held back entries
+    pub fn flush(&mut self) -> Vec<LogEntry> {
+        std::mem::take(&mut self.pending)
+            .into_values()
+            .map(|group| group.entry)
+            .collect()
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    // Timestamp of the oldest line held back
+    pub fn pending_since(&self) -> Option<DateTime<Utc>> {
+        self.pending
+            .values()
+            .map(|group| group.entry.timestamp)
+            .min()
+    }
+}
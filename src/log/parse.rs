@@ -0,0 +1,268 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use super::log_entry::{LogEntry, LogSeverity, LogValue};
+use super::log_processor::{LogProcessor, ProcessedLogs};
+
+// Format of the messages to parse
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseFormat {
+    Json,
+    Logfmt,
+    // JSON for messages starting with `{`, logfmt for the others
+    #[default]
+    Auto,
+}
+
+// Where the parsed fields go
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseTarget {
+    // Flattened into attributes such as `user.id`, the body is the message field
+    #[default]
+    Attributes,
+    // A map body, as nested as the message
+    Body,
+    Both,
+}
+
+// Fields moved onto the entry itself, the first one present is used. An empty
+// list turns the promotion off.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromotedFields {
+    pub timestamp: Vec<String>,
+    pub level: Vec<String>,
+    pub message: Vec<String>,
+    pub trace_id: Vec<String>,
+    pub span_id: Vec<String>,
+}
+
+impl Default for PromotedFields {
+    fn default() -> Self {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        Self {
+            timestamp: names(&["timestamp", "time", "ts", "@timestamp"]),
+            level: names(&["level", "severity", "lvl", "levelname"]),
+            message: names(&["message", "msg"]),
+            trace_id: names(&["trace_id", "traceId", "trace.id"]),
+            span_id: names(&["span_id", "spanId", "span.id"]),
+        }
+    }
+}
+
+// Turns JSON or logfmt messages into structured bodies and attributes. The
+// message is kept as the raw line, and entries which do not parse are left
+// as they are.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BodyParser {
+    pub format: ParseFormat,
+    pub target: ParseTarget,
+    pub promote: PromotedFields,
+}
+
+impl BodyParser {
+    pub fn apply(&self, entry: &mut LogEntry) {
+        let Some(mut fields) = self.parse(&entry.message) else {
+            return;
+        };
+        let promoted = self.promote(entry, &fields);
+
+        if matches!(self.target, ParseTarget::Body | ParseTarget::Both) {
+            entry.body = Some(LogValue::Map(fields.clone()));
+        }
+        if matches!(self.target, ParseTarget::Attributes | ParseTarget::Both) {
+            // Promoted fields are not repeated as attributes
+            for name in promoted {
+                fields.remove(&name);
+            }
+            for (name, value) in fields {
+                flatten_into(&mut entry.attributes, name, value);
+            }
+        }
+    }
+
+    pub fn parse(&self, message: &str) -> Option<BTreeMap<String, LogValue>> {
+        let message = message.trim();
+        match self.format {
+            ParseFormat::Json => parse_json(message),
+            ParseFormat::Logfmt => parse_logfmt(message),
+            ParseFormat::Auto if message.starts_with('{') => parse_json(message),
+            ParseFormat::Auto => parse_logfmt(message),
+        }
+    }
+
+    // Move the promoted fields onto the entry, returning the names used
+    fn promote(&self, entry: &mut LogEntry, fields: &BTreeMap<String, LogValue>) -> Vec<String> {
+        let mut promoted = Vec::new();
+        let mut find = |names: &[String]| {
+            let (name, value) = names
+                .iter()
+                .find_map(|name| Some((name.clone(), fields.get(name)?)))?;
+            promoted.push(name);
+            Some(value)
+        };
+
+        if let Some(timestamp) = find(&self.promote.timestamp).and_then(timestamp_of) {
+            entry.timestamp = timestamp;
+        }
+        if let Some(severity) = find(&self.promote.level).and_then(severity_of) {
+            entry.severity = severity;
+        }
+        if let Some(LogValue::String(message)) = find(&self.promote.message) {
+            // A map body already holds the message
+            if self.target == ParseTarget::Attributes {
+                entry.body = Some(LogValue::String(message.clone()));
+            }
+        }
+        if let Some(LogValue::String(trace_id)) = find(&self.promote.trace_id) {
+            entry.trace_id = Some(trace_id.clone());
+        }
+        if let Some(LogValue::String(span_id)) = find(&self.promote.span_id) {
+            entry.span_id = Some(span_id.clone());
+        }
+
+        promoted
+    }
+}
+
+impl LogProcessor for BodyParser {
+    fn process(&self, batch: &mut ProcessedLogs) {
+        for entry in &mut batch.logs {
+            self.apply(entry);
+        }
+    }
+}
+
+fn timestamp_of(value: &LogValue) -> Option<DateTime<Utc>> {
+    match value {
+        LogValue::String(text) => DateTime::parse_from_rfc3339(text)
+            .ok()
+            .map(|timestamp| timestamp.with_timezone(&Utc)),
+        // Seconds, or milliseconds as written by JavaScript loggers
+        LogValue::Int(epoch) if *epoch > 100_000_000_000 => {
+            Utc.timestamp_millis_opt(*epoch).single()
+        }
+        LogValue::Int(epoch) => Utc.timestamp_opt(*epoch, 0).single(),
+        LogValue::Double(epoch) => Utc
+            .timestamp_opt(epoch.trunc() as i64, (epoch.fract() * 1e9) as u32)
+            .single(),
+        _ => None,
+    }
+}
+
+fn severity_of(value: &LogValue) -> Option<LogSeverity> {
+    match value {
+        LogValue::String(name) => LogSeverity::from_name(name),
+        LogValue::Int(level) => LogSeverity::from_number(*level),
+        _ => None,
+    }
+}
+
+// Nested maps become dotted names, e.g. {"user":{"id":1}} gives user.id
+fn flatten_into(attributes: &mut BTreeMap<String, LogValue>, name: String, value: LogValue) {
+    match value {
+        LogValue::Map(fields) => {
+            for (field, value) in fields {
+                flatten_into(attributes, format!("{}.{}", name, field), value);
+            }
+        }
+        value => {
+            attributes.insert(name, value);
+        }
+    }
+}
+
+fn parse_json(message: &str) -> Option<BTreeMap<String, LogValue>> {
+    match serde_json::from_str::<Value>(message).ok()? {
+        Value::Object(fields) => Some(
+            fields
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, json_value(value)?)))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+// JSON values as log values, null has no counterpart and is dropped
+fn json_value(value: Value) -> Option<LogValue> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => LogValue::Bool(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => LogValue::Int(i),
+            None => LogValue::Double(n.as_f64()?),
+        },
+        Value::String(s) => LogValue::String(s),
+        Value::Array(values) => {
+            LogValue::Array(values.into_iter().filter_map(json_value).collect())
+        }
+        Value::Object(fields) => LogValue::Map(
+            fields
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, json_value(value)?)))
+                .collect(),
+        ),
+    })
+}
+
+// Parse `key=value key="quoted value" flag` pairs. A message with anything
+// else, or without a single key=value pair, is not logfmt.
+fn parse_logfmt(message: &str) -> Option<BTreeMap<String, LogValue>> {
+    let mut fields = BTreeMap::new();
+    let mut has_pair = false;
+    let mut chars = message.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| *c != '=' && !c.is_whitespace()) {
+            if !(c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '@')) {
+                return None;
+            }
+            key.push(c);
+        }
+        if key.is_empty() {
+            return None;
+        }
+
+        if chars.next_if_eq(&'=').is_none() {
+            fields.insert(key, LogValue::Bool(true));
+            continue;
+        }
+        has_pair = true;
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        'n' => value.push('\n'),
+                        't' => value.push('\t'),
+                        c => value.push(c),
+                    },
+                    c => value.push(c),
+                }
+            }
+            if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                return None;
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+        fields.insert(key, LogValue::String(value));
+    }
+
+    has_pair.then_some(fields)
+}
@@ -0,0 +1,15 @@
+use crate::log::log_processor::{LogProcessor, ProcessedLogs};
+
+use super::log_match::LogMatch;
+
+// Drops the entries matching the condition, e.g. health checks
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropProcessor {
+    pub when: LogMatch,
+}
+
+impl LogProcessor for DropProcessor {
+    fn process(&self, batch: &mut ProcessedLogs) {
+        batch.logs.retain(|entry| !self.when.matches(entry));
+    }
+}
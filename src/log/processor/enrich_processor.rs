@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+use crate::log::log_entry::LogValue;
+use crate::log::log_processor::{LogProcessor, ProcessedLogs};
+
+use super::log_match::LogMatch;
+
+// Adds attributes to the matching entries, replacing the ones they may have
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnrichProcessor {
+    pub when: LogMatch,
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl LogProcessor for EnrichProcessor {
+    fn process(&self, batch: &mut ProcessedLogs) {
+        for entry in &mut batch.logs {
+            if self.when.matches(entry) {
+                for (name, value) in &self.attributes {
+                    entry
+                        .attributes
+                        .insert(name.clone(), LogValue::String(value.clone()));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use crate::log::log_processor::{LogProcessor, ProcessedLogs};
+
+use super::log_match::LogMatch;
+
+// Keeps only the entries matching the condition
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterProcessor {
+    pub when: LogMatch,
+}
+
+impl LogProcessor for FilterProcessor {
+    fn process(&self, batch: &mut ProcessedLogs) {
+        batch.logs.retain(|entry| self.when.matches(entry));
+    }
+}
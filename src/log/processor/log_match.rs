@@ -0,0 +1,40 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::log::log_entry::{LogEntry, LogSeverity, LogValue};
+use crate::log::severity::LinePattern;
+
+// Condition on an entry, every field set must match. An empty condition
+// matches every entry.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogMatch {
+    pub message: Option<LinePattern>,
+    // Lowest severity matching, e.g. warn matches warn, error and fatal
+    pub severity: Option<LogSeverity>,
+    // Patterns for the values of attributes, an entry without the attribute
+    // does not match
+    pub attributes: BTreeMap<String, LinePattern>,
+}
+
+impl LogMatch {
+    pub fn is_empty(&self) -> bool {
+        self.message.is_none() && self.severity.is_none() && self.attributes.is_empty()
+    }
+
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.message
+            .as_ref()
+            .is_none_or(|message| message.is_match(&entry.message))
+            && self
+                .severity
+                .is_none_or(|severity| entry.severity >= severity)
+            && self.attributes.iter().all(|(name, pattern)| {
+                entry
+                    .attributes
+                    .get(name)
+                    .and_then(LogValue::as_text)
+                    .is_some_and(|value| pattern.is_match(&value))
+            })
+    }
+}
@@ -0,0 +1,50 @@
+use crate::log::log_entry::LogValue;
+use crate::log::log_processor::{LogProcessor, ProcessedLogs};
+use crate::log::severity::LinePattern;
+
+use super::log_match::LogMatch;
+
+// Rewrites the message, and the body when it is a string, of the matching
+// entries with a regular expression, e.g. to redact tokens. With an attribute
+// set, the string value of that attribute is rewritten instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapProcessor {
+    pub when: LogMatch,
+    pub pattern: LinePattern,
+    // May refer to capture groups as $1 or ${name}
+    pub replacement: String,
+    pub attribute: Option<String>,
+}
+
+impl MapProcessor {
+    fn rewrite(&self, text: &mut String) {
+        if let std::borrow::Cow::Owned(rewritten) =
+            self.pattern.replace_all(text, &self.replacement)
+        {
+            *text = rewritten;
+        }
+    }
+}
+
+impl LogProcessor for MapProcessor {
+    fn process(&self, batch: &mut ProcessedLogs) {
+        for entry in &mut batch.logs {
+            if !self.when.matches(entry) {
+                continue;
+            }
+            match &self.attribute {
+                Some(name) => {
+                    if let Some(LogValue::String(value)) = entry.attributes.get_mut(name) {
+                        self.rewrite(value);
+                    }
+                }
+                None => {
+                    self.rewrite(&mut entry.message);
+                    if let Some(LogValue::String(body)) = &mut entry.body {
+                        self.rewrite(body);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,35 @@
+pub mod drop_processor;
+pub mod enrich_processor;
+pub mod filter_processor;
+pub mod log_match;
+pub mod map_processor;
+pub mod route_processor;
+
+use self::{
+    drop_processor::DropProcessor, enrich_processor::EnrichProcessor,
+    filter_processor::FilterProcessor, map_processor::MapProcessor,
+    route_processor::RouteProcessor,
+};
+use super::log_processor::{LogProcessor, ProcessedLogs};
+
+// The processors which can be declared in the config
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuiltinProcessor {
+    Filter(FilterProcessor),
+    Drop(DropProcessor),
+    Map(MapProcessor),
+    Enrich(EnrichProcessor),
+    Route(RouteProcessor),
+}
+
+impl LogProcessor for BuiltinProcessor {
+    fn process(&self, batch: &mut ProcessedLogs) {
+        match self {
+            Self::Filter(processor) => processor.process(batch),
+            Self::Drop(processor) => processor.process(batch),
+            Self::Map(processor) => processor.process(batch),
+            Self::Enrich(processor) => processor.process(batch),
+            Self::Route(processor) => processor.process(batch),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::log::log_processor::{LogProcessor, ProcessedLogs};
+
+use super::log_match::LogMatch;
+
+// Sends the matching entries to other sinks, instead of the sink of the
+// pipeline unless they are copied
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteProcessor {
+    pub when: LogMatch,
+    pub sinks: Vec<String>,
+    pub copy: bool,
+}
+
+impl LogProcessor for RouteProcessor {
+    fn process(&self, batch: &mut ProcessedLogs) {
+        let mut kept = Vec::with_capacity(batch.logs.len());
+        for entry in std::mem::take(&mut batch.logs) {
+            if !self.when.matches(&entry) {
+                kept.push(entry);
+                continue;
+            }
+            for sink in &self.sinks {
+                batch
+                    .routed
+                    .entry(sink.clone())
+                    .or_default()
+                    .push(entry.clone());
+            }
+            if self.copy {
+                kept.push(entry);
+            }
+        }
+        batch.logs = kept;
+    }
+}
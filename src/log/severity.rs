@@ -0,0 +1,257 @@
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use super::log_entry::{LogEntry, LogSeverity};
+use super::log_processor::{LogProcessor, ProcessedLogs};
+
+impl LogSeverity {
+    // Parse a level name as written by common loggers, e.g. "warning" or "CRIT"
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "trace" | "verbose" => Some(Self::Trace),
+            "debug" | "dbg" => Some(Self::Debug),
+            "info" | "information" | "informational" | "notice" => Some(Self::Info),
+            "warn" | "warning" => Some(Self::Warn),
+            "error" | "err" => Some(Self::Error),
+            "fatal" | "critical" | "crit" | "panic" | "alert" | "emerg" | "emergency" => {
+                Some(Self::Fatal)
+            }
+            _ => None,
+        }
+    }
+
+    // Numeric levels of pino and bunyan, 30 being info
+    pub fn from_number(level: i64) -> Option<Self> {
+        match level {
+            i64::MIN..=10 => Some(Self::Trace),
+            11..=20 => Some(Self::Debug),
+            21..=30 => Some(Self::Info),
+            31..=40 => Some(Self::Warn),
+            41..=50 => Some(Self::Error),
+            _ => Some(Self::Fatal),
+        }
+    }
+
+    // OpenTelemetry severity text
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "UNSPECIFIED",
+            Self::Trace => "TRACE",
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+            Self::Fatal => "FATAL",
+        }
+    }
+}
+
+impl TryFrom<String> for LogSeverity {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, String> {
+        Self::from_name(&name).ok_or_else(|| format!("unknown severity {:?}", name))
+    }
+}
+
+impl<'de> Deserialize<'de> for LogSeverity {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+// Regular expression matched against a whole log line
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct LinePattern {
+    pattern: String,
+    regex: Regex,
+}
+
+impl LinePattern {
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, line: &str) -> bool {
+        self.regex.is_match(line)
+    }
+
+    pub fn replace_all<'a>(&self, line: &'a str, replacement: &str) -> Cow<'a, str> {
+        self.regex.replace_all(line, replacement)
+    }
+}
+
+impl TryFrom<String> for LinePattern {
+    type Error = String;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        let regex = Regex::new(&pattern)
+            .map_err(|e| format!("invalid regular expression {:?}: {}", pattern, e))?;
+        Ok(Self { pattern, regex })
+    }
+}
+
+impl PartialEq for LinePattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.pattern == other.pattern
+    }
+}
+
+// Lines matching the pattern get the level
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeverityRule {
+    pub pattern: LinePattern,
+    pub level: LogSeverity,
+}
+
+// Tells the severity of the lines of a service. Rules are tried first, then
+// the built-in detection, and lines nothing matched get the default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeverityClassifier {
+    pub rules: Vec<SeverityRule>,
+    pub detect: bool,
+    pub default: LogSeverity,
+}
+
+impl Default for SeverityClassifier {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            detect: true,
+            default: LogSeverity::Unspecified,
+        }
+    }
+}
+
+impl SeverityClassifier {
+    pub fn classify(&self, line: &str) -> LogSeverity {
+        self.rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(line))
+            .map(|rule| rule.level)
+            .or_else(|| self.detect.then(|| detect(line)).flatten())
+            .unwrap_or(self.default)
+    }
+
+    // Classify an entry, unless an earlier stage already did
+    pub fn apply(&self, entry: &mut LogEntry) {
+        if entry.severity == LogSeverity::Unspecified {
+            entry.severity = self.classify(&entry.message);
+        }
+    }
+}
+
+impl LogProcessor for SeverityClassifier {
+    fn process(&self, batch: &mut ProcessedLogs) {
+        for entry in &mut batch.logs {
+            self.apply(entry);
+        }
+    }
+}
+
+// Detect the severity of a line from common log formats and crash reports
+pub fn detect(line: &str) -> Option<LogSeverity> {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with('{') {
+        if let Some(severity) = json_level(trimmed) {
+            return Some(severity);
+        }
+    }
+
+    let patterns = patterns();
+    if let Some(captures) = patterns.logfmt.captures(line) {
+        if let Some(severity) = LogSeverity::from_name(&captures[1]) {
+            return Some(severity);
+        }
+    }
+    if let Some(captures) = patterns
+        .bracketed
+        .captures(line)
+        .or_else(|| patterns.prefix.captures(line))
+    {
+        if let Some(severity) = LogSeverity::from_name(&captures[1]) {
+            return Some(severity);
+        }
+    }
+
+    patterns
+        .crashes
+        .iter()
+        .find(|(regex, _)| regex.is_match(line))
+        .map(|(_, severity)| *severity)
+}
+
+// Level field of a JSON line, e.g. {"level":"error"} or {"level":50}
+fn json_level(line: &str) -> Option<LogSeverity> {
+    let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(line) else {
+        return None;
+    };
+    ["level", "severity", "lvl", "levelname", "log.level"]
+        .iter()
+        .find_map(|name| match fields.get(*name)? {
+            Value::String(level) => LogSeverity::from_name(level),
+            Value::Number(level) => LogSeverity::from_number(level.as_i64()?),
+            _ => None,
+        })
+}
+
+struct Patterns {
+    logfmt: Regex,
+    bracketed: Regex,
+    prefix: Regex,
+    crashes: Vec<(Regex, LogSeverity)>,
+}
+
+const LEVELS: &str =
+    "trace|verbose|debug|dbg|info|notice|warn|warning|error|err|fatal|critical|crit|panic";
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let regex = |pattern: &str| Regex::new(pattern).unwrap();
+        Patterns {
+            // level=error, level="warn" or lvl=info anywhere in the line
+            logfmt: regex(r#"(?i)(?:^|\s)(?:level|lvl|severity)="?([a-z]+)"?(?:\s|$)"#),
+            // [ERROR], [warn] or <info> early in the line, e.g. after a timestamp
+            bracketed: regex(&format!(r"(?i)^\S*(?:\s+\S+)?\s*[\[<]({})[\]>]", LEVELS)),
+            // ERROR: or WARN followed by a space, optionally after a timestamp
+            prefix: regex(&format!(
+                r"^(?:[0-9][0-9:.,/+TZ-]*\s+){{0,2}}({})(?::|\s|$)",
+                LEVELS.to_uppercase()
+            )),
+            crashes: vec![
+                // Rust
+                (regex(r"^thread '.*' panicked at"), LogSeverity::Fatal),
+                // Go
+                (regex(r"^(panic|fatal error): "), LogSeverity::Fatal),
+                (regex(r"^goroutine \d+ \[.*\]:$"), LogSeverity::Fatal),
+                (
+                    regex(r"^\t\S+\.go:\d+( \+0x[0-9a-f]+)?$"),
+                    LogSeverity::Fatal,
+                ),
+                // Python
+                (
+                    regex(r"^Traceback \(most recent call last\):"),
+                    LogSeverity::Error,
+                ),
+                (regex(r#"^\s+File ".*", line \d+"#), LogSeverity::Error),
+                // Java, and Node for the stack frames
+                (regex(r"^Exception in thread "), LogSeverity::Error),
+                (regex(r"^Caused by: "), LogSeverity::Error),
+                (regex(r"^\s+at \S.*(\(.*\)|:\d+:\d+)$"), LogSeverity::Error),
+                (regex(r"^\s+\.\.\. \d+ more$"), LogSeverity::Error),
+                // Last line of a Python traceback or first line of a Java one,
+                // e.g. ValueError: bad or java.lang.IllegalStateException: boom
+                (
+                    regex(r"^([\w$]+\.)*[A-Z][\w$]*(Error|Exception)(: |$)"),
+                    LogSeverity::Error,
+                ),
+            ],
+        }
+    })
+}
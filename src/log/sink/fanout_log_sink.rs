@@ -0,0 +1,55 @@
+use crate::log::log_entry::LogEntry;
+use crate::log::log_sink::LogSink;
+use anyhow::Error;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+// Stores every batch in all of the wrapped sinks. The batch only counts as
+// stored when every sink accepted it, so a failing sink gets it again on the
+// next poll, at the cost of duplicates in the others.
+pub struct FanoutLogSink {
+    sinks: Vec<Arc<dyn LogSink>>,
+}
+
+impl FanoutLogSink {
+    pub fn new(sinks: Vec<Arc<dyn LogSink>>) -> Self {
+        Self { sinks }
+    }
+
+    // Combine the errors of every sink into one
+    fn result(&self, errors: Vec<String>) -> Result<(), Error> {
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "{} of {} sinks failed: {}",
+                errors.len(),
+                self.sinks.len(),
+                errors.join("; ")
+            ))
+        }
+    }
+}
+
+#[async_trait]
+impl LogSink for FanoutLogSink {
+    async fn store_logs(&self, logs: Vec<LogEntry>) -> Result<(), Error> {
+        let mut errors = Vec::new();
+        for sink in &self.sinks {
+            if let Err(e) = sink.store_logs(logs.clone()).await {
+                errors.push(e.to_string());
+            }
+        }
+        self.result(errors)
+    }
+
+    async fn flush(&self) -> Result<(), Error> {
+        let mut errors = Vec::new();
+        for sink in &self.sinks {
+            if let Err(e) = sink.flush().await {
+                errors.push(e.to_string());
+            }
+        }
+        self.result(errors)
+    }
+}
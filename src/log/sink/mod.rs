@@ -1 +1,4 @@
+pub mod fanout_log_sink;
+pub mod multiline_log_sink;
 pub mod otlp_log_sink;
+pub mod processing_log_sink;
@@ -0,0 +1,59 @@
+use crate::log::log_entry::LogEntry;
+use crate::log::log_sink::LogSink;
+use crate::log::multiline::{MultilineCombiner, MultilineRule};
+use anyhow::Error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+use tokio::time::Instant;
+
+// Combines multiline entries such as stack traces before handing them over to
+// the wrapped sink. Entries held back for more lines are stored on a later poll
+// or when the sink is flushed, until then the collector keeps its checkpoint
+// before them.
+pub struct MultilineLogSink {
+    sink: Arc<dyn LogSink>,
+    combiner: Mutex<MultilineCombiner>,
+}
+
+impl MultilineLogSink {
+    pub fn new(sink: Arc<dyn LogSink>, rule: MultilineRule) -> Self {
+        Self {
+            sink,
+            combiner: Mutex::new(MultilineCombiner::new(rule)),
+        }
+    }
+
+    fn combiner(&self) -> std::sync::MutexGuard<'_, MultilineCombiner> {
+        self.combiner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl LogSink for MultilineLogSink {
+    async fn store_logs(&self, logs: Vec<LogEntry>) -> Result<(), Error> {
+        // Combined on a copy which is only kept once the batch is stored, so
+        // a batch which failed or timed out combines the same way when the
+        // collector returns it again
+        let mut combiner = self.combiner().clone();
+        let complete = combiner.push(logs, Instant::now());
+
+        self.sink.store_logs(complete).await?;
+        *self.combiner() = combiner;
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), Error> {
+        // Only dropped once stored, so a failed flush can be tried again
+        let pending = self.combiner().clone().flush();
+        if !pending.is_empty() {
+            self.sink.store_logs(pending).await?;
+            self.combiner().flush();
+        }
+        self.sink.flush().await
+    }
+
+    fn held_back_since(&self) -> Option<DateTime<Utc>> {
+        self.combiner().pending_since()
+    }
+}
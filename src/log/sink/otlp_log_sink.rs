@@ -1,42 +1,79 @@
-use crate::log::log_entry::LogEntry;
+use crate::log::log_entry::{LogEntry, LogSeverity, LogValue};
 use crate::log::log_sink::LogSink;
 use anyhow::Error;
 use async_trait::async_trait;
-use opentelemetry::logs::{LogRecord as OtlpLogRecord, Severity};
-use opentelemetry::KeyValue;
+use opentelemetry::logs::{AnyValue, LogRecord as OtlpLogRecord, Severity};
+use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState};
+use opentelemetry::{Key, KeyValue};
 use opentelemetry_otlp::{HttpExporterBuilder, LogExporter as OtlpLogExporter, WithExportConfig};
 use opentelemetry_sdk::export::logs::{LogBatch, LogExporter};
-use opentelemetry_sdk::logs::LogRecord;
+use opentelemetry_sdk::logs::{LogRecord, TraceContext};
 use opentelemetry_sdk::{InstrumentationLibrary, Resource};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::Duration;
 use tokio::sync::Mutex;
 
+// Where and how the OTLP HTTP exporter sends logs. Unset fields fall back to
+// the standard OTEL_EXPORTER_OTLP_* environment variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpHttpOptions {
+    pub endpoint: Option<String>,
+    pub headers: HashMap<String, String>,
+    pub timeout: Duration,
+}
+
+impl Default for OtlpHttpOptions {
+    fn default() -> Self {
+        Self {
+            endpoint: None,
+            headers: HashMap::new(),
+            timeout: Duration::from_secs(3),
+        }
+    }
+}
+
 // Define the OtlpLogSink struct with resource information
 pub struct OtlpLogSink {
     exporter: Arc<Mutex<Box<OtlpLogExporter>>>,
+    // Added to the resource of every record, over the labels of its source
+    labels: HashMap<String, String>,
 }
 
 impl OtlpLogSink {
     // New constructor using HTTP protocol for vector.dev
     pub fn new_http(labels: HashMap<String, String>) -> Result<Self, Error> {
-        let mut exporter = HttpExporterBuilder::default()
-            .with_timeout(std::time::Duration::from_secs(3))
-            .build_log_exporter()?;
-
-        let kvs: Vec<KeyValue> = labels
-            .into_iter()
-            .map(|(k, v)| KeyValue::new(k, v))
-            .collect();
-        let resource = Resource::new(kvs);
+        Self::new_http_with(&OtlpHttpOptions::default(), labels)
+    }
 
-        exporter.set_resource(&resource);
+    pub fn new_http_with(
+        options: &OtlpHttpOptions,
+        labels: HashMap<String, String>,
+    ) -> Result<Self, Error> {
+        let mut builder = HttpExporterBuilder::default()
+            .with_timeout(options.timeout)
+            .with_headers(options.headers.clone());
+        if let Some(endpoint) = &options.endpoint {
+            builder = builder.with_endpoint(endpoint.clone());
+        }
+        let exporter = builder.build_log_exporter()?;
 
         Ok(OtlpLogSink {
             exporter: Arc::new(Mutex::new(Box::new(exporter))),
+            labels,
         })
     }
+
+    // The resource of a record: the labels of its source, when it has one,
+    // overlaid by the labels of the sink
+    fn resource_labels(&self, entry: &LogEntry) -> BTreeMap<String, String> {
+        let mut labels = BTreeMap::new();
+        if !entry.source.service_id.is_empty() {
+            labels.extend(entry.source.labels());
+        }
+        labels.extend(self.labels.clone());
+        labels
+    }
 }
 
 #[async_trait]
@@ -48,27 +85,69 @@ impl LogSink for OtlpLogSink {
                 .with_schema_url("https://opentelemetry.io/schemas/1.25.0")
                 .build();
 
-        let log_records: Vec<(LogRecord, InstrumentationLibrary)> = logs
-            .iter()
-            .map(|entry| {
-                let record: LogRecord = entry.into();
-                (record, instrumentation_library.clone())
-            })
-            .collect();
-
-        let log_records_slice: Vec<(&LogRecord, &InstrumentationLibrary)> =
-            log_records.iter().map(|(r, i)| (r, i)).collect();
-
-        let log_batch = LogBatch::new(&log_records_slice);
+        // The exporter has one resource per batch, so lines of different
+        // sources go out in separate batches
+        let mut batches: BTreeMap<
+            BTreeMap<String, String>,
+            Vec<(LogRecord, InstrumentationLibrary)>,
+        > = BTreeMap::new();
+        for entry in &logs {
+            batches
+                .entry(self.resource_labels(entry))
+                .or_default()
+                .push((entry.into(), instrumentation_library.clone()));
+        }
 
         // Lock the exporter and get a mutable reference
         let mut guard = self.exporter.lock().await;
         let exporter = guard.as_mut();
 
-        let result = exporter.export(log_batch).await;
-        match result {
-            Ok(_) => Ok(()),
-            Err(e) => Err(anyhow::anyhow!("Error exporting logs: {}", e)),
+        for (labels, log_records) in batches {
+            let kvs: Vec<KeyValue> = labels
+                .into_iter()
+                .map(|(k, v)| KeyValue::new(k, v))
+                .collect();
+            exporter.set_resource(&Resource::new(kvs));
+
+            let log_records_slice: Vec<(&LogRecord, &InstrumentationLibrary)> =
+                log_records.iter().map(|(r, i)| (r, i)).collect();
+            let log_batch = LogBatch::new(&log_records_slice);
+
+            if let Err(e) = exporter.export(log_batch).await {
+                return Err(anyhow::anyhow!("Error exporting logs: {}", e));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn severity_number(severity: LogSeverity) -> Severity {
+    match severity {
+        LogSeverity::Trace => Severity::Trace,
+        LogSeverity::Debug => Severity::Debug,
+        LogSeverity::Unspecified | LogSeverity::Info => Severity::Info,
+        LogSeverity::Warn => Severity::Warn,
+        LogSeverity::Error => Severity::Error,
+        LogSeverity::Fatal => Severity::Fatal,
+    }
+}
+
+impl From<LogValue> for AnyValue {
+    fn from(value: LogValue) -> Self {
+        match value {
+            LogValue::String(s) => AnyValue::String(s.into()),
+            LogValue::Bool(b) => AnyValue::Boolean(b),
+            LogValue::Int(i) => AnyValue::Int(i),
+            LogValue::Double(d) => AnyValue::Double(d),
+            LogValue::Bytes(b) => AnyValue::Bytes(Box::new(b)),
+            LogValue::Array(values) => {
+                AnyValue::ListAny(Box::new(values.into_iter().map(Into::into).collect()))
+            }
+            LogValue::Map(map) => AnyValue::Map(Box::new(
+                map.into_iter()
+                    .map(|(k, v)| (Key::new(k), v.into()))
+                    .collect(),
+            )),
         }
     }
 }
@@ -76,12 +155,46 @@ impl LogSink for OtlpLogSink {
 // Implement From<LogEntry> for LogRecord
 impl From<&LogEntry> for LogRecord {
     fn from(entry: &LogEntry) -> Self {
-        let now = SystemTime::now();
         let mut log_record = LogRecord::default();
-        log_record.set_body(entry.message.clone().into());
+        log_record.set_body(entry.body().into());
         log_record.set_timestamp(entry.timestamp.into());
-        log_record.set_observed_timestamp(now.into());
-        log_record.set_severity_number(Severity::Info);
+        log_record.set_observed_timestamp(entry.observed_timestamp.into());
+        // Lines which were not classified are sent as info, as they always were
+        log_record.set_severity_number(severity_number(entry.severity));
+        if entry.severity != LogSeverity::Unspecified {
+            log_record.set_severity_text(entry.severity.as_str());
+        }
+
+        if let Some(trace_id) = entry
+            .trace_id
+            .as_deref()
+            .and_then(|id| TraceId::from_hex(id).ok())
+        {
+            let span_id = entry
+                .span_id
+                .as_deref()
+                .and_then(|id| SpanId::from_hex(id).ok())
+                .unwrap_or(SpanId::INVALID);
+            let span_context = SpanContext::new(
+                trace_id,
+                span_id,
+                TraceFlags::default(),
+                false,
+                TraceState::default(),
+            );
+            log_record.trace_context = Some(TraceContext::from(&span_context));
+        }
+
+        for (key, value) in &entry.attributes {
+            log_record.add_attribute(key.clone(), AnyValue::from(value.clone()));
+        }
+        // Vary from line to line, so they are attributes rather than resource
+        if let Some(deployment_id) = &entry.source.deployment_id {
+            log_record.add_attribute("deployment_id", deployment_id.clone());
+        }
+        if let Some(instance) = &entry.source.instance {
+            log_record.add_attribute("zeabur_uid", instance.clone());
+        }
         log_record
     }
 }
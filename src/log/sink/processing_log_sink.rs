@@ -0,0 +1,82 @@
+use crate::log::log_entry::LogEntry;
+use crate::log::log_processor::{LogProcessor, ProcessedLogs};
+use crate::log::log_sink::LogSink;
+use anyhow::Error;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+// Runs every batch through a chain of processors, then stores what is left
+// in the wrapped sink and the routed entries in the sinks they were routed
+// to. As with FanoutLogSink, the batch only counts as stored when every sink
+// accepted its part.
+pub struct ProcessingLogSink {
+    sink: Arc<dyn LogSink>,
+    processors: Vec<Arc<dyn LogProcessor>>,
+    // Sinks entries can be routed to, by name
+    routes: BTreeMap<String, Arc<dyn LogSink>>,
+}
+
+impl ProcessingLogSink {
+    pub fn new(sink: Arc<dyn LogSink>, processors: Vec<Arc<dyn LogProcessor>>) -> Self {
+        Self {
+            sink,
+            processors,
+            routes: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_routes(mut self, routes: BTreeMap<String, Arc<dyn LogSink>>) -> Self {
+        self.routes = routes;
+        self
+    }
+
+    fn result(errors: Vec<String>) -> Result<(), Error> {
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(anyhow::anyhow!("{}", errors.join("; "))),
+        }
+    }
+}
+
+#[async_trait]
+impl LogSink for ProcessingLogSink {
+    async fn store_logs(&self, logs: Vec<LogEntry>) -> Result<(), Error> {
+        let mut batch = ProcessedLogs::new(logs);
+        for processor in &self.processors {
+            processor.process(&mut batch);
+        }
+
+        let mut errors = Vec::new();
+        if let Err(e) = self.sink.store_logs(batch.logs).await {
+            errors.push(e.to_string());
+        }
+        for (name, logs) in batch.routed {
+            let Some(sink) = self.routes.get(&name) else {
+                log::warn!(
+                    "Dropping {} logs routed to unknown sink {}",
+                    logs.len(),
+                    name
+                );
+                continue;
+            };
+            if let Err(e) = sink.store_logs(logs).await {
+                errors.push(format!("sink {}: {}", name, e));
+            }
+        }
+        Self::result(errors)
+    }
+
+    async fn flush(&self) -> Result<(), Error> {
+        let mut errors = Vec::new();
+        if let Err(e) = self.sink.flush().await {
+            errors.push(e.to_string());
+        }
+        for (name, sink) in &self.routes {
+            if let Err(e) = sink.flush().await {
+                errors.push(format!("sink {}: {}", name, e));
+            }
+        }
+        Self::result(errors)
+    }
+}
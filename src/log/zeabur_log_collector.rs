@@ -1,17 +1,86 @@
-use super::{log_collector::LogCollector, log_entry::LogEntry};
-use crate::zeabur::client::ZeaburClient;
+use super::{
+    checkpoint_store::{Checkpoint, CheckpointStore},
+    dedup_window::{DedupKey, DedupWindow},
+    log_collector::{LogCollector, PollLoad},
+    log_entry::{LogEntry, LogSource},
+};
+use crate::zeabur::{client::ZeaburClient, service_key::ServiceKey};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::{Arc, RwLock},
+};
 use tokio::sync::Mutex;
 
+// How the collector reads the runtime logs of a service
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollectorMode {
+    // Only read the latest window of runtime logs on every poll
+    Tail,
+    // Also walk back with timestampCursor until the last checkpoint (or
+    // `since` when there is none) is reached, fetching at most `page_budget`
+    // pages per poll so one noisy service cannot starve the others
+    Backfill {
+        since: Option<DateTime<Utc>>,
+        page_budget: usize,
+    },
+}
+
+// Range of logs between the committed cursor and the oldest line read so far
+// which still has to be walked back through
+#[derive(Debug, Clone, PartialEq)]
+struct Gap {
+    floor: DateTime<Utc>,
+    cursor: DateTime<Utc>,
+}
+
+// Cursor state applied once the sink has confirmed a batch
+struct PendingCursor {
+    last_timestamp: Option<DateTime<Utc>>,
+    gaps: VecDeque<Gap>,
+    keys: Vec<DedupKey>,
+}
+
+// Cursor of the collector: what has been committed and what is awaiting commit
+#[derive(Default)]
+struct CursorState {
+    // Whether the committed timestamp has been loaded from the checkpoint store
+    loaded: bool,
+    last_timestamp: Option<DateTime<Utc>>,
+    // Gaps still to walk back through, oldest first. A gap opening while an
+    // older one is walked is queued behind it.
+    gaps: VecDeque<Gap>,
+    pending: Option<PendingCursor>,
+    // Last timestamp written to the checkpoint store
+    checkpoint: Option<DateTime<Utc>>,
+    // Lines already shipped, used to tell repeats from lines sharing a timestamp
+    shipped: DedupWindow,
+    // Load observed by the last poll
+    load: PollLoad,
+}
+
+impl CursorState {
+    // Position that is safe to resume from after a restart: everything after
+    // it has been shipped, unless a backfill gap is still open
+    fn durable_timestamp(&self) -> Option<DateTime<Utc>> {
+        match self.gaps.iter().map(|gap| gap.floor).min() {
+            Some(floor) => Some(floor),
+            None => self.last_timestamp,
+        }
+    }
+}
+
 // Define the ZeaburServiceLogCollector struct
 pub struct ZeaburServiceLogCollector {
-    project_id: String,
-    service_id: String,
-    environment_id: String,
+    key: ServiceKey,
     client: ZeaburClient,
-    last_timestamp: Arc<Mutex<Option<DateTime<Utc>>>>,
+    mode: CollectorMode,
+    page_size: usize,
+    checkpoint_store: Option<Arc<dyn CheckpointStore>>,
+    cursor: Arc<Mutex<CursorState>>,
+    // Stamped on every line, with the zeaburUID of the line as instance
+    source: RwLock<LogSource>,
 }
 
 // Implement the LogCollector trait for ZeaburServiceLogCollector
@@ -20,10 +89,42 @@ impl LogCollector for ZeaburServiceLogCollector {
     async fn collect_logs(&self) -> Result<Vec<LogEntry>, anyhow::Error> {
         self.fetch_logs().await
     }
+
+    async fn commit(&self, held_back: Option<DateTime<Utc>>) -> Result<(), anyhow::Error> {
+        let mut cursor = self.cursor.lock().await;
+
+        if let Some(pending) = cursor.pending.take() {
+            cursor.last_timestamp = pending.last_timestamp;
+            cursor.gaps = pending.gaps;
+            for key in pending.keys {
+                cursor.shipped.insert(key);
+            }
+        }
+
+        // Lines the sink still holds back are read again after a restart
+        let durable = match (cursor.durable_timestamp(), held_back) {
+            (Some(durable), Some(held_back)) => Some(durable.min(held_back)),
+            (durable, _) => durable,
+        };
+        if let (Some(store), Some(timestamp)) = (&self.checkpoint_store, durable) {
+            if Some(timestamp) != cursor.checkpoint {
+                store.commit(&self.key, &Checkpoint { timestamp }).await?;
+                cursor.checkpoint = Some(timestamp);
+            }
+        }
+        Ok(())
+    }
+
+    async fn poll_load(&self) -> PollLoad {
+        self.cursor.lock().await.load
+    }
 }
 
 // Constructor and methods for ZeaburServiceLogCollector
 impl ZeaburServiceLogCollector {
+    // Number of lines from which a runtimeLogs page is considered full
+    pub const DEFAULT_PAGE_SIZE: usize = 1000;
+
     // Constructor remains unchanged
     pub fn new(
         project_id: String,
@@ -31,58 +132,223 @@ impl ZeaburServiceLogCollector {
         service_id: String,
         client: ZeaburClient,
     ) -> Self {
+        let source = LogSource {
+            project_id: project_id.clone(),
+            environment_id: environment_id.clone(),
+            service_id: service_id.clone(),
+            ..LogSource::default()
+        };
         Self {
-            project_id,
-            service_id,
-            environment_id,
+            key: ServiceKey::new(project_id, environment_id, service_id),
             client,
-            last_timestamp: Arc::new(Mutex::new(None)),
+            mode: CollectorMode::Tail,
+            page_size: Self::DEFAULT_PAGE_SIZE,
+            checkpoint_store: None,
+            cursor: Arc::new(Mutex::new(CursorState::default())),
+            source: RwLock::new(source),
+        }
+    }
+
+    pub fn with_mode(mut self, mode: CollectorMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    // A head page with at least this many lines may have overflowed, so the
+    // service should be polled more often
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    // Persist the cursor in the given store, and resume from it on the first poll
+    pub fn with_checkpoint_store(mut self, store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoint_store = Some(store);
+        self
+    }
+
+    // Bound the number of shipped lines remembered for deduplication
+    pub fn with_dedup_capacity(self, capacity: usize) -> Self {
+        Self {
+            cursor: Arc::new(Mutex::new(CursorState {
+                shipped: DedupWindow::new(capacity),
+                ..CursorState::default()
+            })),
+            ..self
         }
     }
 
+    pub fn with_source(self, source: LogSource) -> Self {
+        self.set_source(source);
+        self
+    }
+
+    // Replace the source stamped on the next lines, e.g. after a new deployment
+    pub fn set_source(&self, source: LogSource) {
+        *self.source.write().unwrap_or_else(|e| e.into_inner()) = source;
+    }
+
+    pub fn source(&self) -> LogSource {
+        self.source
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn key(&self) -> &ServiceKey {
+        &self.key
+    }
+
+    // Timestamp of the latest log whose delivery has been committed
+    pub async fn last_timestamp(&self) -> Option<DateTime<Utc>> {
+        self.cursor.lock().await.last_timestamp
+    }
+
+    // Whether older logs are still being walked back through
+    pub async fn is_backfilling(&self) -> bool {
+        !self.cursor.lock().await.gaps.is_empty()
+    }
+
     // Updated to use LogCollectorError
     async fn fetch_logs(&self) -> Result<Vec<LogEntry>, anyhow::Error> {
-        // Retrieve the current last_timestamp
-        let mut last_timestamp = self.last_timestamp.lock().await;
+        // Retrieve the current cursor
+        let mut cursor = self.cursor.lock().await;
+
+        if !cursor.loaded {
+            if let Some(store) = &self.checkpoint_store {
+                cursor.last_timestamp = store.load(&self.key).await?.map(|c| c.timestamp);
+                cursor.checkpoint = cursor.last_timestamp;
+            }
+            cursor.loaded = true;
+        }
+
+        let (since, page_budget) = match &self.mode {
+            CollectorMode::Tail => (None, 1),
+            CollectorMode::Backfill { since, page_budget } => (*since, (*page_budget).max(1)),
+        };
+        let last_timestamp = cursor.last_timestamp;
+        let lower_bound = last_timestamp.or(since);
+        let mut gaps = cursor.gaps.clone();
+        let mut batch_keys = HashSet::new();
+
+        // Read the latest window of logs first
+        let head = self.fetch_page(None).await?;
+        let mut logs: Vec<(DedupKey, LogEntry)> = head
+            .iter()
+            .filter(|(key, _)| {
+                // Filter out logs older than the last_timestamp; lines sharing
+                // the last_timestamp are checked against the dedup window
+                lower_bound.is_none_or(|lower| key.timestamp >= lower)
+                    && !cursor.shipped.contains(key)
+                    && batch_keys.insert(key.clone())
+            })
+            .cloned()
+            .collect();
+
+        // The head window does not reach back to what was shipped before, so
+        // there may be logs in between that have to be walked back through.
+        // Gaps are walked in the order they opened.
+        if page_budget > 1 {
+            if let (Some(lower), Some((oldest, _))) = (lower_bound, head.first()) {
+                if oldest.timestamp > lower {
+                    gaps.push_back(Gap {
+                        floor: lower,
+                        cursor: oldest.timestamp,
+                    });
+                }
+            }
+        }
+
+        let mut pages_left = page_budget - 1;
+        while let (Some(open), true) = (gaps.front().cloned(), pages_left > 0) {
+            pages_left -= 1;
+            let page = self.fetch_page(Some(open.cursor)).await?;
+
+            logs.extend(
+                page.iter()
+                    .filter(|(key, _)| {
+                        key.timestamp >= open.floor
+                            && !cursor.shipped.contains(key)
+                            && batch_keys.insert(key.clone())
+                    })
+                    .cloned(),
+            );
+
+            // The gap is closed once a page reaches the floor or stops making progress
+            match page.first() {
+                Some((oldest, _))
+                    if oldest.timestamp > open.floor && oldest.timestamp < open.cursor =>
+                {
+                    gaps[0].cursor = oldest.timestamp;
+                }
+                _ => {
+                    gaps.pop_front();
+                }
+            }
+        }
+
+        // Sort logs by timestamp to ensure we get the latest
+        logs.sort_by_key(|(_, log)| log.timestamp);
+
+        // Lines may have been missed when the head page is full, when it does
+        // not reach back to the previous poll, or while a gap is still open
+        let overlaps_previous = match (last_timestamp, head.first()) {
+            (Some(last), Some((oldest, _))) => oldest.timestamp <= last,
+            _ => true,
+        };
+        cursor.load = if head.len() >= self.page_size || !overlaps_previous || !gaps.is_empty() {
+            PollLoad::Saturated
+        } else if logs.is_empty() {
+            PollLoad::Idle
+        } else {
+            PollLoad::Steady
+        };
+
+        // Only move the cursor once the sink has confirmed the batch
+        let head_latest = head.last().map(|(key, _)| key.timestamp);
+        let (keys, logs): (Vec<DedupKey>, Vec<LogEntry>) = logs.into_iter().unzip();
+        cursor.pending = Some(PendingCursor {
+            last_timestamp: last_timestamp.max(head_latest),
+            gaps,
+            keys,
+        });
+
+        Ok(logs)
+    }
 
+    // Fetch one page of runtime logs, oldest first
+    async fn fetch_page(
+        &self,
+        timestamp_cursor: Option<DateTime<Utc>>,
+    ) -> Result<Vec<(DedupKey, LogEntry)>, anyhow::Error> {
         let runtime_logs = self
             .client
             .query_service_runtime_logs(
-                &self.project_id,
-                &self.service_id,
-                &self.environment_id,
-                None,
+                &self.key.project_id,
+                &self.key.service_id,
+                &self.key.environment_id,
+                timestamp_cursor,
             )
             .await?;
 
-        let mut logs: Vec<LogEntry> = runtime_logs
+        let observed_timestamp = Utc::now();
+        let source = self.source();
+        let mut page: Vec<(DedupKey, LogEntry)> = runtime_logs
             .into_iter()
             .filter_map(|log| {
                 let timestamp = DateTime::parse_from_rfc3339(&log.timestamp).ok()?;
                 let utc_timestamp = timestamp.with_timezone(&Utc);
-
-                // Filter out logs older than or equal to the last_timestamp
-                if let Some(last) = *last_timestamp {
-                    if utc_timestamp <= last {
-                        return None;
-                    }
-                }
-
-                Some(LogEntry {
-                    timestamp: utc_timestamp,
-                    message: log.message,
-                })
+                let key = DedupKey::new(utc_timestamp, &log.zeabur_uid, &log.message);
+                let mut entry = LogEntry::new(utc_timestamp, log.message).with_source(LogSource {
+                    instance: Some(log.zeabur_uid),
+                    ..source.clone()
+                });
+                entry.observed_timestamp = observed_timestamp;
+                Some((key, entry))
             })
             .collect();
 
-        // Sort logs by timestamp to ensure we get the latest
-        logs.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-
-        // Update last_timestamp if we have new logs
-        if let Some(latest_log) = logs.last() {
-            *last_timestamp = Some(latest_log.timestamp);
-        }
-
-        Ok(logs)
+        page.sort_by_key(|(key, _)| key.timestamp);
+        Ok(page)
     }
 }
@@ -1,38 +1,177 @@
-use anyhow::{Context, Result};
-use reqwest::Client;
+use reqwest::{header::RETRY_AFTER, Client, Response, StatusCode};
+use serde::de::DeserializeOwned;
 use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::client_builder::ZeaburClientBuilder;
+use super::error::ZeaburError;
+use super::graphql::{GraphQLErrorBody, GraphQLResponse};
+use super::rate_limit::{Operation, RateLimiter, RateLimits};
+use super::retry::RetryPolicy;
+
+pub const DEFAULT_ENDPOINT: &str = "https://gateway.zeabur.com/graphql";
 
 #[derive(Clone)]
 pub struct ZeaburClient {
     api_key: String,
+    endpoint: String,
     client: Client,
+    retry_policy: RetryPolicy,
+    // Shared by all clones, so the budget applies to the whole process
+    rate_limiter: Arc<RateLimiter>,
 }
 
 impl ZeaburClient {
     pub fn new(api_key: String) -> Self {
-        let client = Client::new();
-        ZeaburClient { api_key, client }
+        Self::builder(api_key)
+            .build()
+            .expect("Default Zeabur client configuration is valid")
+    }
+
+    pub fn builder(api_key: String) -> ZeaburClientBuilder {
+        ZeaburClientBuilder::new(api_key)
     }
 
-    pub(crate) async fn execute_query(
+    pub(crate) fn from_parts(
+        api_key: String,
+        endpoint: String,
+        client: Client,
+        retry_policy: RetryPolicy,
+        rate_limits: &RateLimits,
+    ) -> Self {
+        ZeaburClient {
+            api_key,
+            endpoint,
+            client,
+            retry_policy,
+            rate_limiter: Arc::new(RateLimiter::new(rate_limits)),
+        }
+    }
+
+    // Run a GraphQL operation and decode its `data` into T
+    pub(crate) async fn execute_query<T: DeserializeOwned>(
         &self,
+        operation: Operation,
         query: &str,
         variables: serde_json::Value,
-    ) -> Result<Value> {
+    ) -> Result<T, ZeaburError> {
+        let mut attempt = 1;
+        loop {
+            let permit = self.rate_limiter.acquire(operation).await;
+            let result = self.send_query(query, &variables).await;
+            drop(permit);
+
+            match result {
+                Err(e)
+                    if attempt < self.retry_policy.max_attempts
+                        && (self.retry_policy.retryable)(&e) =>
+                {
+                    let delay = self.retry_policy.delay_for(attempt, &e);
+                    log::warn!(
+                        "Zeabur API call failed (attempt {}/{}), retrying in {:?}: {}",
+                        attempt,
+                        self.retry_policy.max_attempts,
+                        delay,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send_query<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: &Value,
+    ) -> Result<T, ZeaburError> {
         let body = serde_json::json!({
             "query": query,
             "variables": variables
         });
 
-        self.client
-            .post("https://gateway.zeabur.com/graphql")
+        let response = self
+            .client
+            .post(&self.endpoint)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .json(&body)
             .send()
-            .await
-            .context("Failed to send request")?
-            .json::<Value>()
-            .await
-            .context("Failed to parse response")
+            .await?;
+
+        let response = check_status(response).await?;
+        let text = response.text().await?;
+
+        // Look at the errors before decoding data, which is usually null then
+        let response: GraphQLResponse<Value> =
+            serde_json::from_str(&text).map_err(|e| ZeaburError::Decode(e.to_string()))?;
+        if let Some(e) = graphql_error(&response.errors) {
+            return Err(e);
+        }
+
+        let data = response
+            .data
+            .ok_or_else(|| ZeaburError::Decode("Response has no data".to_string()))?;
+        serde_json::from_value(data).map_err(|e| ZeaburError::Decode(e.to_string()))
+    }
+}
+
+// Map HTTP level failures onto ZeaburError
+async fn check_status(response: Response) -> Result<Response, ZeaburError> {
+    let status = response.status();
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(ZeaburError::Unauthorized),
+        StatusCode::TOO_MANY_REQUESTS => Err(ZeaburError::RateLimited {
+            retry_after: retry_after(&response),
+        }),
+        _ if status.is_success() => Ok(response),
+        _ => Err(ZeaburError::Http {
+            status,
+            body: response.text().await.unwrap_or_default(),
+        }),
     }
 }
+
+// Retry-After is only honoured in its delay-seconds form
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+// Map the GraphQL `errors` array onto ZeaburError, if it is not empty
+fn graphql_error(errors: &[GraphQLErrorBody]) -> Option<ZeaburError> {
+    if errors.is_empty() {
+        return None;
+    }
+
+    let unauthenticated = errors
+        .iter()
+        .any(|error| matches!(error.code(), Some("UNAUTHENTICATED") | Some("FORBIDDEN")));
+    if unauthenticated {
+        return Some(ZeaburError::Unauthorized);
+    }
+
+    let messages = errors.iter().map(|error| error.message.clone()).collect();
+    let path = errors
+        .iter()
+        .find(|error| !error.path.is_empty())
+        .map(|error| {
+            error
+                .path
+                .iter()
+                .map(|segment| match segment {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(ZeaburError::GraphQL { messages, path })
+}
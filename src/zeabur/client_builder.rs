@@ -0,0 +1,146 @@
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, Proxy};
+use std::time::Duration;
+
+use super::client::{ZeaburClient, DEFAULT_ENDPOINT};
+use super::error::ZeaburError;
+use super::rate_limit::RateLimits;
+use super::retry::RetryPolicy;
+
+pub const DEFAULT_USER_AGENT: &str = concat!("zeabur-ops/", env!("CARGO_PKG_VERSION"));
+
+// Builder for a ZeaburClient with a non-default transport configuration
+pub struct ZeaburClientBuilder {
+    api_key: String,
+    endpoint: String,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxy: Option<String>,
+    headers: Vec<(String, String)>,
+    user_agent: String,
+    root_certificates_pem: Vec<Vec<u8>>,
+    built_in_root_certificates: bool,
+    retry_policy: RetryPolicy,
+    rate_limits: RateLimits,
+}
+
+impl ZeaburClientBuilder {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            endpoint: DEFAULT_ENDPOINT.to_string(),
+            connect_timeout: Some(Duration::from_secs(10)),
+            timeout: Some(Duration::from_secs(30)),
+            proxy: None,
+            headers: Vec::new(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            root_certificates_pem: Vec::new(),
+            built_in_root_certificates: true,
+            retry_policy: RetryPolicy::default(),
+            rate_limits: RateLimits::default(),
+        }
+    }
+
+    // GraphQL endpoint, e.g. a regional gateway or a local mock server
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    // Timeout of a whole request, from connecting until the body is read
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // Proxy used for all requests, instead of the one from the environment
+    pub fn proxy(mut self, proxy_url: impl Into<String>) -> Self {
+        self.proxy = Some(proxy_url.into());
+        self
+    }
+
+    // Extra header sent with every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    // Trust an additional PEM encoded root certificate, e.g. a corporate CA
+    pub fn add_root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates_pem.push(pem.into());
+        self
+    }
+
+    // Whether the bundled web PKI root certificates are trusted
+    pub fn built_in_root_certificates(mut self, enabled: bool) -> Self {
+        self.built_in_root_certificates = enabled;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn rate_limits(mut self, rate_limits: RateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    pub fn build(self) -> Result<ZeaburClient, ZeaburError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| ZeaburError::Config(format!("Invalid header name {}: {}", name, e)))?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                ZeaburError::Config(format!("Invalid value for header {}: {}", name, e))
+            })?;
+            headers.append(name, value);
+        }
+
+        let mut builder = Client::builder()
+            .user_agent(&self.user_agent)
+            .default_headers(headers)
+            .tls_built_in_root_certs(self.built_in_root_certificates);
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy_url) = &self.proxy {
+            let proxy = Proxy::all(proxy_url).map_err(|e| {
+                ZeaburError::Config(format!("Invalid proxy URL {}: {}", proxy_url, e))
+            })?;
+            builder = builder.proxy(proxy);
+        }
+        for pem in &self.root_certificates_pem {
+            let certificate = Certificate::from_pem(pem)
+                .map_err(|e| ZeaburError::Config(format!("Invalid root certificate: {}", e)))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        let client = builder
+            .build()
+            .map_err(|e| ZeaburError::Config(format!("Failed to build HTTP client: {}", e)))?;
+
+        Ok(ZeaburClient::from_parts(
+            self.api_key,
+            self.endpoint,
+            client,
+            self.retry_policy,
+            &self.rate_limits,
+        ))
+    }
+}
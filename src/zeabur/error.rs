@@ -0,0 +1,46 @@
+use reqwest::StatusCode;
+use std::time::Duration;
+use thiserror::Error;
+
+// Errors returned by the Zeabur API client
+#[derive(Debug, Error)]
+pub enum ZeaburError {
+    #[error("Unauthorized: the Zeabur API key was rejected")]
+    Unauthorized,
+
+    #[error("Rate limited by the Zeabur API{}", retry_after.map(|d| format!(", retry after {}s", d.as_secs())).unwrap_or_default())]
+    RateLimited { retry_after: Option<Duration> },
+
+    #[error("GraphQL error{}: {}", if path.is_empty() { String::new() } else { format!(" at {}", path.join(".")) }, messages.join("; "))]
+    GraphQL {
+        messages: Vec<String>,
+        path: Vec<String>,
+    },
+
+    #[error("Unexpected HTTP status {status}: {body}")]
+    Http { status: StatusCode, body: String },
+
+    #[error("Failed to send request: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    #[error("Failed to decode response: {0}")]
+    Decode(String),
+
+    #[error("Invalid client configuration: {0}")]
+    Config(String),
+}
+
+impl ZeaburError {
+    // Whether the same request may succeed when sent again later
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ZeaburError::RateLimited { .. } => true,
+            ZeaburError::Http { status, .. } => status.is_server_error(),
+            ZeaburError::Transport(e) => e.is_timeout() || e.is_connect() || e.is_request(),
+            ZeaburError::Unauthorized
+            | ZeaburError::GraphQL { .. }
+            | ZeaburError::Decode(_)
+            | ZeaburError::Config(_) => false,
+        }
+    }
+}
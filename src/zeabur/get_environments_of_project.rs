@@ -1,9 +1,9 @@
 // Imports
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 
 use super::client::ZeaburClient;
+use super::error::ZeaburError;
+use super::rate_limit::Operation;
 
 // Struct definitions
 #[derive(Debug, Serialize, Deserialize)]
@@ -21,9 +21,18 @@ pub struct Environment {
     pub name: String,
 }
 
+// Shape of the `data` field of the response
+#[derive(Debug, Deserialize)]
+struct GetEnvironmentsOfProjectData {
+    project: Option<Project>,
+}
+
 // Implementation
 impl ZeaburClient {
-    pub async fn get_environments_of_project(&self, project_id: &str) -> Result<Project> {
+    pub async fn get_environments_of_project(
+        &self,
+        project_id: &str,
+    ) -> Result<Project, ZeaburError> {
         // GraphQL query
         let query = r#"
         query GetEnvironmentsOfProject($projectID: ObjectID!) {
@@ -44,31 +53,10 @@ impl ZeaburClient {
         });
 
         // Execute the query
-        let response = self.execute_query(query, variables).await?;
-        self.parse_project_environments(response)
-    }
-
-    // Helper function to parse the response
-    fn parse_project_environments(&self, response: Value) -> Result<Project> {
-        response
-            .as_object()
-            .and_then(|obj| obj.get("data"))
-            .and_then(|data| data.get("project"))
-            .ok_or_else(|| anyhow::anyhow!("Invalid response format"))
-            .and_then(|project| {
-                Ok(Project {
-                    id: project["_id"].as_str().unwrap_or("").to_string(),
-                    name: project["name"].as_str().unwrap_or("").to_string(),
-                    environments: project["environments"]
-                        .as_array()
-                        .unwrap_or(&Vec::new())
-                        .iter()
-                        .map(|env| Environment {
-                            id: env["_id"].as_str().unwrap_or("").to_string(),
-                            name: env["name"].as_str().unwrap_or("").to_string(),
-                        })
-                        .collect(),
-                })
-            })
+        let data: GetEnvironmentsOfProjectData = self
+            .execute_query(Operation::Discovery, query, variables)
+            .await?;
+        data.project
+            .ok_or_else(|| ZeaburError::Decode(format!("Project {} not found", project_id)))
     }
 }
@@ -1,12 +1,14 @@
 // Imports
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::client::ZeaburClient;
+use super::error::ZeaburError;
+use super::rate_limit::Operation;
 
 // Struct definitions
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Service {
     #[serde(rename = "_id")]
     pub id: String,
@@ -19,17 +21,22 @@ pub struct Service {
     pub spec: Option<ServiceSpec>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Deployment {
+    #[serde(rename = "_id", default)]
+    pub id: Option<String>,
     pub plan_type: Option<String>,
     pub plan_meta: Option<Value>,
     pub status: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct MarketplaceItem {
     pub name: String,
     pub code: String,
+    #[serde(rename = "iconURL")]
     pub icon_url: String,
     pub network_type: Option<String>,
 }
@@ -39,13 +46,24 @@ pub struct ServiceSpec {
     pub icon: Option<String>,
 }
 
+// Shape of the `data` field of the response
+#[derive(Debug, Deserialize)]
+struct GetServicesOfProjectData {
+    project: Option<ProjectServices>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectServices {
+    services: Vec<Service>,
+}
+
 // Implementation
 impl ZeaburClient {
     pub async fn get_services_of_project(
         &self,
         project_id: &str,
         environment_id: &str,
-    ) -> Result<Vec<Service>> {
+    ) -> Result<Vec<Service>, ZeaburError> {
         // GraphQL query
         let query = r#"
         query GetServicesOfProject($projectID: ObjectID!, $environmentID: ObjectID!) {
@@ -55,6 +73,7 @@ impl ZeaburClient {
               name
               onceProduct
               latestDeployment(environmentID: $environmentID) {
+                _id
                 planType
                 planMeta
                 status
@@ -82,47 +101,11 @@ impl ZeaburClient {
         });
 
         // Execute the query
-        let response = self.execute_query(query, variables).await?;
-        self.parse_services(response)
-    }
-
-    // Helper function to parse the response
-    fn parse_services(&self, response: Value) -> Result<Vec<Service>> {
-        response
-            .as_object()
-            .and_then(|obj| obj.get("data"))
-            .and_then(|data| data.get("project"))
-            .and_then(|project| project.get("services"))
-            .and_then(|services| services.as_array())
-            .ok_or_else(|| anyhow::anyhow!("Invalid response format"))?
-            .iter()
-            .map(|service| {
-                Ok(Service {
-                    id: service["_id"].as_str().unwrap_or("").to_string(),
-                    name: service["name"].as_str().unwrap_or("").to_string(),
-                    once_product: service["onceProduct"].as_bool(),
-                    latest_deployment: service["latestDeployment"].as_object().map(|deployment| {
-                        Deployment {
-                            plan_type: deployment["planType"].as_str().map(String::from),
-                            plan_meta: Some(deployment["planMeta"].clone()),
-                            status: deployment["status"].as_str().map(String::from),
-                        }
-                    }),
-                    template: service["template"].as_str().map(String::from),
-                    market_item_code: service["marketItemCode"].as_str().map(String::from),
-                    marketplace_item: service["marketplaceItem"].as_object().map(|item| {
-                        MarketplaceItem {
-                            name: item["name"].as_str().unwrap_or("").to_string(),
-                            code: item["code"].as_str().unwrap_or("").to_string(),
-                            icon_url: item["iconURL"].as_str().unwrap_or("").to_string(),
-                            network_type: item["networkType"].as_str().map(String::from),
-                        }
-                    }),
-                    spec: service["spec"].as_object().map(|spec| ServiceSpec {
-                        icon: spec["icon"].as_str().map(String::from),
-                    }),
-                })
-            })
-            .collect()
+        let data: GetServicesOfProjectData = self
+            .execute_query(Operation::Discovery, query, variables)
+            .await?;
+        data.project
+            .map(|project| project.services)
+            .ok_or_else(|| ZeaburError::Decode(format!("Project {} not found", project_id)))
     }
 }
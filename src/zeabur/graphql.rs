@@ -0,0 +1,45 @@
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+// Envelope of every GraphQL response
+#[derive(Debug, Deserialize)]
+pub struct GraphQLResponse<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GraphQLErrorBody>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphQLErrorBody {
+    pub message: String,
+    #[serde(default)]
+    pub path: Vec<Value>,
+    #[serde(default)]
+    pub extensions: Option<Value>,
+}
+
+impl GraphQLErrorBody {
+    pub fn code(&self) -> Option<&str> {
+        self.extensions.as_ref()?.get("code")?.as_str()
+    }
+}
+
+// Relay style connection, as returned by paginated queries
+#[derive(Debug, Deserialize)]
+pub struct Connection<T> {
+    pub edges: Vec<Edge<T>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Edge<T> {
+    pub node: T,
+}
+
+// Accept an explicit null for fields which are not required
+pub(crate) fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
@@ -1,8 +1,9 @@
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
 
 use super::client::ZeaburClient;
+use super::error::ZeaburError;
+use super::graphql::{null_as_default, Connection};
+use super::rate_limit::Operation;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Project {
@@ -20,7 +21,7 @@ pub struct Project {
     pub collaborator_avatar_urls: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Region {
     pub provider: String,
     pub name: String,
@@ -34,8 +35,58 @@ pub struct Environment {
     pub name: String,
 }
 
+// Shape of the `data` field of the response
+#[derive(Debug, Deserialize)]
+struct ListProjectsData {
+    projects: Connection<ProjectNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProjectNode {
+    name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    description: String,
+    #[serde(rename = "iconURL", default, deserialize_with = "null_as_default")]
+    icon_url: String,
+    #[serde(rename = "_id")]
+    id: String,
+    region: Region,
+    environments: Vec<Environment>,
+    owner: Option<Avatar>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    collaborators: Vec<Avatar>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Avatar {
+    #[serde(rename = "avatarURL")]
+    avatar_url: Option<String>,
+}
+
+impl From<ProjectNode> for Project {
+    fn from(node: ProjectNode) -> Self {
+        Project {
+            name: node.name,
+            description: node.description,
+            icon_url: node.icon_url,
+            id: node.id,
+            region: node.region,
+            environments: node.environments,
+            owner_avatar_url: node
+                .owner
+                .and_then(|owner| owner.avatar_url)
+                .unwrap_or_default(),
+            collaborator_avatar_urls: node
+                .collaborators
+                .into_iter()
+                .filter_map(|collab| collab.avatar_url)
+                .collect(),
+        }
+    }
+}
+
 impl ZeaburClient {
-    pub async fn list_projects(&self) -> Result<Vec<Project>> {
+    pub async fn list_projects(&self) -> Result<Vec<Project>, ZeaburError> {
         let query = r#"
         query GetProjects {
           projects {
@@ -68,56 +119,14 @@ impl ZeaburClient {
 
         let variables = serde_json::json!({});
 
-        let response = self.execute_query(query, variables).await?;
-        self.parse_projects(response)
-    }
-
-    fn parse_projects(&self, response: Value) -> Result<Vec<Project>> {
-        response
-            .as_object()
-            .and_then(|obj| obj.get("data"))
-            .and_then(|data| data.get("projects"))
-            .and_then(|projects| projects.get("edges"))
-            .and_then(|edges| edges.as_array())
-            .ok_or_else(|| anyhow::anyhow!("Invalid response format"))?
-            .iter()
-            .filter_map(|edge| edge.get("node"))
-            .map(|node| {
-                Ok(Project {
-                    name: node["name"].as_str().unwrap_or("").to_string(),
-                    description: node["description"].as_str().unwrap_or("").to_string(),
-                    icon_url: node["iconURL"].as_str().unwrap_or("").to_string(),
-                    id: node["_id"].as_str().unwrap_or("").to_string(),
-                    region: Region {
-                        provider: node["region"]["provider"]
-                            .as_str()
-                            .unwrap_or("")
-                            .to_string(),
-                        name: node["region"]["name"].as_str().unwrap_or("").to_string(),
-                        id: node["region"]["id"].as_str().unwrap_or("").to_string(),
-                    },
-                    environments: node["environments"]
-                        .as_array()
-                        .unwrap_or(&Vec::new())
-                        .iter()
-                        .map(|env| Environment {
-                            id: env["_id"].as_str().unwrap_or("").to_string(),
-                            name: env["name"].as_str().unwrap_or("").to_string(),
-                        })
-                        .collect(),
-                    owner_avatar_url: node["owner"]["avatarURL"]
-                        .as_str()
-                        .unwrap_or("")
-                        .to_string(),
-                    collaborator_avatar_urls: node["collaborators"]
-                        .as_array()
-                        .unwrap_or(&Vec::new())
-                        .iter()
-                        .filter_map(|collab| collab["avatarURL"].as_str())
-                        .map(String::from)
-                        .collect(),
-                })
-            })
-            .collect()
+        let data: ListProjectsData = self
+            .execute_query(Operation::Discovery, query, variables)
+            .await?;
+        Ok(data
+            .projects
+            .edges
+            .into_iter()
+            .map(|edge| edge.node.into())
+            .collect())
     }
 }
@@ -1,5 +1,13 @@
 pub mod client;
+pub mod client_builder;
+pub mod error;
 pub mod get_environments_of_project;
 pub mod get_services_of_project;
+pub mod graphql;
 pub mod list_projects;
 pub mod query_service_runtime_logs;
+pub mod rate_limit;
+pub mod retry;
+pub mod runtime_log_range;
+pub mod service_key;
+pub mod topology;
@@ -0,0 +1,171 @@
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+// Kind of Zeabur API call, each kind has its own budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    // Listing projects, environments and services
+    Discovery,
+    // Reading runtime logs
+    Logs,
+}
+
+// Budget for one kind of API call
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationLimit {
+    // Sustained request rate, refilling the token bucket
+    pub requests_per_second: f64,
+    // Size of the token bucket, i.e. how many requests may be sent at once
+    pub burst: u32,
+    // Maximum number of requests in flight at the same time
+    pub max_concurrency: usize,
+}
+
+impl OperationLimit {
+    pub fn unlimited() -> Self {
+        Self {
+            requests_per_second: f64::INFINITY,
+            burst: u32::MAX,
+            max_concurrency: Semaphore::MAX_PERMITS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimits {
+    pub discovery: OperationLimit,
+    pub logs: OperationLimit,
+}
+
+impl RateLimits {
+    pub fn unlimited() -> Self {
+        Self {
+            discovery: OperationLimit::unlimited(),
+            logs: OperationLimit::unlimited(),
+        }
+    }
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            discovery: OperationLimit {
+                requests_per_second: 2.0,
+                burst: 5,
+                max_concurrency: 2,
+            },
+            logs: OperationLimit {
+                requests_per_second: 10.0,
+                burst: 20,
+                max_concurrency: 8,
+            },
+        }
+    }
+}
+
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<BucketState>,
+}
+
+// Longest wait for a token, rates below one token per this long are raised to it
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+impl TokenBucket {
+    fn new(rate: f64, capacity: u32) -> Self {
+        let capacity = f64::from(capacity.max(1));
+        // An infinite rate turns the bucket off, rates that are not positive
+        // would never refill it
+        let min_rate = 1.0 / MAX_WAIT.as_secs_f64();
+        let rate = if rate == f64::INFINITY || (rate.is_finite() && rate >= min_rate) {
+            rate
+        } else {
+            min_rate
+        };
+        Self {
+            rate,
+            capacity,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    // Wait until a token is available and take it
+    async fn acquire(&self) {
+        if self.rate.is_infinite() {
+            return;
+        }
+
+        loop {
+            let wait = {
+                let mut state = self.state.lock().await;
+                let now = Instant::now();
+                let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+                state.tokens = (state.tokens + elapsed * self.rate).min(self.capacity);
+                state.refilled_at = now;
+
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+                Duration::try_from_secs_f64((1.0 - state.tokens) / self.rate)
+                    .map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT))
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+struct Limiter {
+    bucket: TokenBucket,
+    in_flight: Semaphore,
+}
+
+impl Limiter {
+    fn new(limit: &OperationLimit) -> Self {
+        Self {
+            bucket: TokenBucket::new(limit.requests_per_second, limit.burst),
+            in_flight: Semaphore::new(limit.max_concurrency.max(1)),
+        }
+    }
+}
+
+// Throttles API calls of a ZeaburClient and all of its clones
+pub(crate) struct RateLimiter {
+    discovery: Limiter,
+    logs: Limiter,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(limits: &RateLimits) -> Self {
+        Self {
+            discovery: Limiter::new(&limits.discovery),
+            logs: Limiter::new(&limits.logs),
+        }
+    }
+
+    // Wait for a concurrency slot and a token; the request may be sent while
+    // the returned permit is held
+    pub(crate) async fn acquire(&self, operation: Operation) -> SemaphorePermit<'_> {
+        let limiter = match operation {
+            Operation::Discovery => &self.discovery,
+            Operation::Logs => &self.logs,
+        };
+        let permit = limiter
+            .in_flight
+            .acquire()
+            .await
+            .expect("Rate limiter semaphore is never closed");
+        limiter.bucket.acquire().await;
+        permit
+    }
+}
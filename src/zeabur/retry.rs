@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use super::error::ZeaburError;
+
+// How ZeaburClient retries failed API calls
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    // Total number of attempts, including the first one
+    pub max_attempts: u32,
+    // Delay before the first retry, doubled on every following retry
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // Fraction of the delay that is randomized, between 0.0 and 1.0
+    pub jitter: f64,
+    // Decides which errors are worth another attempt
+    pub retryable: fn(&ZeaburError) -> bool,
+}
+
+impl RetryPolicy {
+    // Never retry, fail on the first error
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    // Delay to wait before the given retry (1 for the first retry). A
+    // Retry-After sent by the API takes precedence over the backoff, up to
+    // the maximum delay.
+    pub fn delay_for(&self, retry: u32, error: &ZeaburError) -> Duration {
+        if let ZeaburError::RateLimited {
+            retry_after: Some(retry_after),
+        } = error
+        {
+            return (*retry_after).min(self.max_delay);
+        }
+
+        let exponent = retry.saturating_sub(1).min(31);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0) * fastrand::f64();
+        backoff.mul_f64(1.0 - jitter)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+            retryable: ZeaburError::is_transient,
+        }
+    }
+}
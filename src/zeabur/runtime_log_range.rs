@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+
+use super::client::ZeaburClient;
+use super::error::ZeaburError;
+use super::query_service_runtime_logs::RuntimeLog;
+use super::service_key::ServiceKey;
+
+// A runtime log with its parsed timestamp
+#[derive(Debug)]
+pub struct TimedRuntimeLog {
+    pub timestamp: DateTime<Utc>,
+    pub log: RuntimeLog,
+}
+
+impl ZeaburClient {
+    // Every runtime log of a service from `since` (inclusive) to `until`
+    // (exclusive), oldest first. Pages are walked back from `until` with
+    // timestampCursor until one reaches `since` or stops making progress.
+    pub async fn query_service_runtime_logs_between(
+        &self,
+        key: &ServiceKey,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<TimedRuntimeLog>, ZeaburError> {
+        let mut logs = Vec::new();
+        let mut seen = HashSet::new();
+        let mut cursor = until;
+
+        while cursor > since {
+            let page = self
+                .query_service_runtime_logs(
+                    &key.project_id,
+                    &key.service_id,
+                    &key.environment_id,
+                    Some(cursor),
+                )
+                .await?;
+
+            let mut oldest = None;
+            for log in page {
+                let Ok(timestamp) = DateTime::parse_from_rfc3339(&log.timestamp) else {
+                    continue;
+                };
+                let timestamp = timestamp.with_timezone(&Utc);
+                oldest =
+                    Some(oldest.map_or(timestamp, |oldest: DateTime<Utc>| oldest.min(timestamp)));
+
+                // Pages may overlap on lines sharing the cursor timestamp
+                let identity = (timestamp, log.zeabur_uid.clone(), log.message.clone());
+                if timestamp >= since && timestamp < until && seen.insert(identity) {
+                    logs.push(TimedRuntimeLog { timestamp, log });
+                }
+            }
+
+            match oldest {
+                Some(oldest) if oldest < cursor => cursor = oldest,
+                _ => break,
+            }
+        }
+
+        logs.sort_by_key(|log| log.timestamp);
+        Ok(logs)
+    }
+}
@@ -0,0 +1,29 @@
+use std::fmt;
+
+// Identity of a single service running in a single environment of a project
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ServiceKey {
+    pub project_id: String,
+    pub environment_id: String,
+    pub service_id: String,
+}
+
+impl ServiceKey {
+    pub fn new(project_id: String, environment_id: String, service_id: String) -> Self {
+        Self {
+            project_id,
+            environment_id,
+            service_id,
+        }
+    }
+}
+
+impl fmt::Display for ServiceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{}/{}",
+            self.project_id, self.environment_id, self.service_id
+        )
+    }
+}
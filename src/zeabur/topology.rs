@@ -0,0 +1,120 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::client::ZeaburClient;
+use super::error::ZeaburError;
+use super::get_services_of_project::Deployment;
+use super::list_projects::{Project, Region};
+use super::service_key::ServiceKey;
+
+// A service deployed in one environment of a project
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredService {
+    pub key: ServiceKey,
+    pub project_name: String,
+    pub region: Region,
+    pub environment_name: String,
+    pub service_name: String,
+    pub latest_deployment: Deployment,
+}
+
+// Every (environment, service) pair visible to the API key
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Topology {
+    pub services: BTreeMap<ServiceKey, DiscoveredService>,
+    // Projects whose services could not be listed, with the reason. Their
+    // services are missing from this topology but may still exist.
+    pub failed_projects: BTreeMap<String, String>,
+}
+
+impl Topology {
+    pub fn is_complete(&self) -> bool {
+        self.failed_projects.is_empty()
+    }
+
+    // Services whose name or ID is `service`, optionally narrowed down by the
+    // name or ID of their project and environment
+    pub fn select(
+        &self,
+        project: Option<&str>,
+        environment: Option<&str>,
+        service: &str,
+    ) -> Vec<&DiscoveredService> {
+        self.services
+            .values()
+            .filter(|s| s.service_name == service || s.key.service_id == service)
+            .filter(|s| project.is_none_or(|p| s.project_name == p || s.key.project_id == p))
+            .filter(|s| {
+                environment.is_none_or(|e| s.environment_name == e || s.key.environment_id == e)
+            })
+            .collect()
+    }
+
+    pub fn project_ids(&self) -> BTreeSet<&str> {
+        self.services
+            .keys()
+            .map(|key| key.project_id.as_str())
+            .collect()
+    }
+}
+
+impl ZeaburClient {
+    // Discover services per environment. A service is only part of an
+    // environment when it has a deployment there, and each pair keeps the
+    // deployment of its own environment.
+    pub async fn discover_topology(&self) -> Result<Topology, ZeaburError> {
+        let projects = self.list_projects().await?;
+        Ok(self.discover_topology_of(projects).await)
+    }
+
+    // Discover the services of the given projects only
+    pub async fn discover_topology_of(&self, projects: Vec<Project>) -> Topology {
+        let mut topology = Topology::default();
+
+        for project in projects {
+            for environment in &project.environments {
+                let services = match self
+                    .get_services_of_project(&project.id, &environment.id)
+                    .await
+                {
+                    Ok(services) => services,
+                    Err(e) => {
+                        log::warn!(
+                            "Failed to list services of project {} in environment {}: {}",
+                            project.id,
+                            environment.id,
+                            e
+                        );
+                        topology
+                            .failed_projects
+                            .insert(project.id.clone(), e.to_string());
+                        continue;
+                    }
+                };
+
+                for service in services {
+                    let Some(latest_deployment) = service.latest_deployment else {
+                        continue;
+                    };
+                    let key = ServiceKey::new(
+                        project.id.clone(),
+                        environment.id.clone(),
+                        service.id.clone(),
+                    );
+                    topology.services.insert(
+                        key.clone(),
+                        DiscoveredService {
+                            key,
+                            project_name: project.name.clone(),
+                            region: project.region.clone(),
+                            environment_name: environment.name.clone(),
+                            service_name: service.name,
+                            latest_deployment,
+                        },
+                    );
+                }
+            }
+        }
+
+        topology
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+use zeabur_ops::daemon::adaptive_interval::AdaptiveInterval;
+use zeabur_ops::log::log_collector::PollLoad;
+
+#[test]
+fn test_interval_backs_off_when_idle_and_speeds_up_when_saturated() {
+    let mut interval = AdaptiveInterval::new(Duration::from_secs(2), Duration::from_secs(10));
+    assert_eq!(interval.current(), Duration::from_secs(2));
+
+    assert_eq!(interval.observe(PollLoad::Idle), Duration::from_secs(3));
+    assert_eq!(interval.observe(PollLoad::Steady), Duration::from_secs(3));
+    assert_eq!(
+        interval.observe(PollLoad::Idle),
+        Duration::from_millis(4500)
+    );
+    for _ in 0..10 {
+        interval.observe(PollLoad::Idle);
+    }
+    assert_eq!(interval.current(), Duration::from_secs(10));
+
+    assert_eq!(
+        interval.observe(PollLoad::Saturated),
+        Duration::from_secs(5)
+    );
+    assert_eq!(
+        interval.observe(PollLoad::Saturated),
+        Duration::from_millis(2500)
+    );
+    assert_eq!(
+        interval.observe(PollLoad::Saturated),
+        Duration::from_secs(2)
+    );
+}
+
+#[test]
+fn test_interval_is_fixed_when_bounds_are_equal() {
+    let mut interval = AdaptiveInterval::new(Duration::from_secs(5), Duration::from_secs(5));
+    assert_eq!(interval.observe(PollLoad::Idle), Duration::from_secs(5));
+    assert_eq!(
+        interval.observe(PollLoad::Saturated),
+        Duration::from_secs(5)
+    );
+}
+
+#[test]
+fn test_maximum_below_the_minimum_is_raised_to_it() {
+    let mut interval = AdaptiveInterval::new(Duration::from_secs(5), Duration::from_secs(1));
+    assert_eq!(interval.current(), Duration::from_secs(5));
+    assert_eq!(interval.observe(PollLoad::Idle), Duration::from_secs(5));
+    assert_eq!(
+        interval.observe(PollLoad::Saturated),
+        Duration::from_secs(5)
+    );
+}
+
+#[test]
+fn test_zero_minimum_is_raised_to_the_floor() {
+    let mut interval = AdaptiveInterval::new(Duration::ZERO, Duration::from_secs(1));
+    assert_eq!(interval.current(), AdaptiveInterval::MIN_INTERVAL);
+    assert_eq!(
+        interval.observe(PollLoad::Saturated),
+        AdaptiveInterval::MIN_INTERVAL
+    );
+    assert!(interval.observe(PollLoad::Idle) > AdaptiveInterval::MIN_INTERVAL);
+}
@@ -0,0 +1,92 @@
+use chrono::{TimeZone, Utc};
+use zeabur_ops::log::checkpoint::file_checkpoint_store::FileCheckpointStore;
+use zeabur_ops::log::checkpoint::open_checkpoint_store;
+use zeabur_ops::log::checkpoint::sqlite_checkpoint_store::SqliteCheckpointStore;
+use zeabur_ops::log::checkpoint_store::{Checkpoint, CheckpointStore};
+use zeabur_ops::zeabur::service_key::ServiceKey;
+
+// Helper function to build a service key
+fn key(service_id: &str) -> ServiceKey {
+    ServiceKey::new(
+        "project".to_string(),
+        "environment".to_string(),
+        service_id.to_string(),
+    )
+}
+
+// Helper function to build a checkpoint at the given second
+fn checkpoint(secs: i64) -> Checkpoint {
+    Checkpoint {
+        timestamp: Utc.timestamp_opt(secs, 0).unwrap(),
+    }
+}
+
+#[tokio::test]
+async fn test_file_checkpoint_store_survives_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("checkpoints.json");
+
+    let store = FileCheckpointStore::open(&path).await.unwrap();
+    assert_eq!(store.load(&key("a")).await.unwrap(), None);
+
+    store.commit(&key("a"), &checkpoint(100)).await.unwrap();
+    store.commit(&key("b"), &checkpoint(200)).await.unwrap();
+    store.commit(&key("a"), &checkpoint(300)).await.unwrap();
+
+    // A fresh store simulates a restart of the process
+    let reopened = FileCheckpointStore::open(&path).await.unwrap();
+    assert_eq!(
+        reopened.load(&key("a")).await.unwrap(),
+        Some(checkpoint(300))
+    );
+    assert_eq!(
+        reopened.load(&key("b")).await.unwrap(),
+        Some(checkpoint(200))
+    );
+    assert_eq!(reopened.load(&key("c")).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_file_checkpoint_store_rejects_corrupt_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("checkpoints.json");
+    std::fs::write(&path, "not json").unwrap();
+
+    assert!(FileCheckpointStore::open(&path).await.is_err());
+}
+
+#[tokio::test]
+async fn test_sqlite_checkpoint_store_survives_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("checkpoints.db");
+
+    let store = SqliteCheckpointStore::open(&path).await.unwrap();
+    assert_eq!(store.load(&key("a")).await.unwrap(), None);
+
+    store.commit(&key("a"), &checkpoint(100)).await.unwrap();
+    store.commit(&key("a"), &checkpoint(300)).await.unwrap();
+    drop(store);
+
+    let reopened = SqliteCheckpointStore::open(&path).await.unwrap();
+    assert_eq!(
+        reopened.load(&key("a")).await.unwrap(),
+        Some(checkpoint(300))
+    );
+    assert_eq!(reopened.load(&key("b")).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_open_checkpoint_store_picks_backend_from_spec() {
+    let dir = tempfile::tempdir().unwrap();
+    let sqlite_spec = format!("sqlite:{}", dir.path().join("cursors.db").display());
+    let file_spec = dir.path().join("cursors.json").display().to_string();
+
+    for spec in [sqlite_spec, file_spec] {
+        let store = open_checkpoint_store(&spec).await.unwrap();
+        store.commit(&key("a"), &checkpoint(42)).await.unwrap();
+        assert_eq!(store.load(&key("a")).await.unwrap(), Some(checkpoint(42)));
+    }
+
+    assert!(dir.path().join("cursors.db").exists());
+    assert!(dir.path().join("cursors.json").exists());
+}
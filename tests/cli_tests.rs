@@ -0,0 +1,272 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Output, Stdio};
+use std::time::{Duration, Instant};
+use wiremock::matchers::{body_partial_json, body_string_contains, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Helper function to answer the Zeabur API with one project running `api` in
+// production and staging
+async fn mock_zeabur(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(body_string_contains("GetProjects"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "projects": { "edges": [{ "node": {
+                "name": "shop",
+                "description": "",
+                "iconURL": "",
+                "_id": "shop-id",
+                "region": { "provider": "aws", "name": "Tokyo", "id": "aws-tokyo-1" },
+                "environments": [
+                    { "_id": "production-id", "name": "production" },
+                    { "_id": "staging-id", "name": "staging" }
+                ],
+                "owner": null,
+                "collaborators": []
+            } }] } }
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("GetServicesOfProject"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "project": { "services": [
+                { "_id": "api-id", "name": "api", "latestDeployment": { "status": "RUNNING" } }
+            ] } }
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("QueryServiceRuntimeLogs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "runtimeLogs": [
+                { "timestamp": "2024-05-01T12:00:02Z", "zeaburUID": "uid", "message": "world" },
+                { "timestamp": "2024-05-01T12:00:01Z", "zeaburUID": "uid", "message": "hello" }
+            ] }
+        })))
+        .mount(server)
+        .await;
+}
+
+// Helper function to write a config pointing at the mock server
+fn write_config(server: &MockServer, dir: &tempfile::TempDir) -> PathBuf {
+    let config = dir.path().join("zeabur-ops.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "[[accounts]]\napi_key = \"secret\"\nendpoint = \"{}\"\n",
+            server.uri()
+        ),
+    )
+    .unwrap();
+    config
+}
+
+// Helper function to run the binary with a config pointing at the mock server
+fn zeabur_ops(server: &MockServer, dir: &tempfile::TempDir, args: &[&str]) -> Output {
+    std::process::Command::new(env!("CARGO_BIN_EXE_zeabur-ops"))
+        .arg("--config")
+        .arg(write_config(server, dir))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_projects_list_as_json() {
+    let server = MockServer::start().await;
+    mock_zeabur(&server).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = zeabur_ops(&server, &dir, &["projects", "list", "--output", "json"]);
+
+    assert!(output.status.success(), "{:?}", output);
+    let projects: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        projects,
+        json!([{
+            "account": "default",
+            "id": "shop-id",
+            "name": "shop",
+            "region": "aws-Tokyo",
+            "environments": ["production", "staging"]
+        }])
+    );
+
+    let output = zeabur_ops(&server, &dir, &["projects", "list", "--account", "other"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No account named other"));
+}
+
+#[tokio::test]
+async fn test_services_list_as_table() {
+    let server = MockServer::start().await;
+    mock_zeabur(&server).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = zeabur_ops(
+        &server,
+        &dir,
+        &[
+            "services",
+            "list",
+            "--project",
+            "shop",
+            "--environment",
+            "staging",
+        ],
+    );
+
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "ENVIRONMENT  SERVICE  SERVICE ID  STATUS\nstaging      api      api-id      RUNNING\n"
+    );
+}
+
+#[tokio::test]
+async fn test_logs_export_as_jsonl() {
+    let server = MockServer::start().await;
+    mock_zeabur(&server).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = zeabur_ops(
+        &server,
+        &dir,
+        &[
+            "logs",
+            "export",
+            "--service",
+            "api",
+            "--environment",
+            "production",
+            "--since",
+            "2024-05-01T12:00:00Z",
+            "--until",
+            "2024-05-01T13:00:00Z",
+        ],
+    );
+
+    assert!(output.status.success(), "{:?}", output);
+    let lines: Vec<Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["message"], "hello");
+    assert_eq!(lines[0]["timestamp"], "2024-05-01T12:00:01+00:00");
+    assert_eq!(lines[0]["environment_id"], "production-id");
+    assert_eq!(lines[1]["message"], "world");
+}
+
+#[tokio::test]
+async fn test_logs_export_rejects_ambiguous_service() {
+    let server = MockServer::start().await;
+    mock_zeabur(&server).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let output = zeabur_ops(
+        &server,
+        &dir,
+        &["logs", "export", "--service", "api", "--since", "1h"],
+    );
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("shop/production/api"), "{}", stderr);
+    assert!(stderr.contains("shop/staging/api"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_logs_tail_merges_services_until_interrupted() {
+    let server = MockServer::start().await;
+    // Mounted first so they take precedence over the runtime logs of mock_zeabur
+    for (environment, logs) in [
+        (
+            "production-id",
+            json!([
+                { "timestamp": "2024-05-01T12:00:01Z", "zeaburUID": "uid", "message": "hello" },
+                { "timestamp": "2024-05-01T12:00:03Z", "zeaburUID": "uid", "message": "GET /healthz" },
+                { "timestamp": "2024-05-01T11:00:00Z", "zeaburUID": "uid", "message": "too old" }
+            ]),
+        ),
+        (
+            "staging-id",
+            json!([
+                { "timestamp": "2024-05-01T12:00:02Z", "zeaburUID": "uid", "message": "{\"level\":\"info\"}" }
+            ]),
+        ),
+    ] {
+        Mock::given(method("POST"))
+            .and(body_string_contains("QueryServiceRuntimeLogs"))
+            .and(body_partial_json(
+                json!({ "variables": { "environmentID": environment } }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "data": { "runtimeLogs": logs } })),
+            )
+            .mount(&server)
+            .await;
+    }
+    mock_zeabur(&server).await;
+    let dir = tempfile::tempdir().unwrap();
+
+    let mut child = std::process::Command::new(env!("CARGO_BIN_EXE_zeabur-ops"))
+        .arg("--config")
+        .arg(write_config(&server, &dir))
+        .args([
+            "logs",
+            "tail",
+            "--service",
+            "api",
+            "--since",
+            "2024-05-01T12:00:00Z",
+            "--exclude",
+            "healthz$",
+            "--pretty",
+            "--color",
+            "never",
+            "--interval",
+            "100ms",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+    let mut lines = Vec::new();
+    for _ in 0..4 {
+        let mut line = String::new();
+        stdout.read_line(&mut line).unwrap();
+        lines.push(line);
+    }
+    assert_eq!(
+        lines.concat(),
+        "production/api 2024-05-01T12:00:01+00:00 hello\n\
+         staging/api    2024-05-01T12:00:02+00:00 {\n\
+         staging/api      \"level\": \"info\"\n\
+         staging/api    }\n"
+    );
+
+    std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        assert!(started.elapsed() < Duration::from_secs(10), "did not exit");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    };
+    assert!(status.success());
+
+    // Lines already printed are not printed again by later polls
+    let mut rest = String::new();
+    std::io::Read::read_to_string(&mut stdout, &mut rest).unwrap();
+    assert_eq!(rest, "");
+}
@@ -0,0 +1,496 @@
+use anyhow::Error;
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use wiremock::matchers::{body_string_contains, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zeabur_ops::config::{Config, ConfigFormat};
+use zeabur_ops::daemon::collection::{Collection, SinkFactory};
+use zeabur_ops::daemon::discovery::AccountTopologies;
+use zeabur_ops::daemon::reload::ReloadSignals;
+use zeabur_ops::log::log_entry::{LogEntry, LogSeverity};
+use zeabur_ops::log::log_sink::LogSink;
+use zeabur_ops::zeabur::get_services_of_project::Deployment;
+use zeabur_ops::zeabur::list_projects::Region;
+use zeabur_ops::zeabur::service_key::ServiceKey;
+use zeabur_ops::zeabur::topology::{DiscoveredService, Topology};
+
+// Sink keeping every stored line in memory, along with what it was built from
+struct MemorySink {
+    endpoints: Vec<Option<String>>,
+    labels: HashMap<String, String>,
+    logs: Mutex<Vec<LogEntry>>,
+    flushes: AtomicUsize,
+}
+
+#[async_trait]
+impl LogSink for MemorySink {
+    async fn store_logs(&self, logs: Vec<LogEntry>) -> Result<(), Error> {
+        self.logs.lock().unwrap().extend(logs);
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<(), Error> {
+        self.flushes.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+// Helper function to build sinks in memory, remembering every one of them
+fn memory_sinks() -> (SinkFactory, Arc<Mutex<Vec<Arc<MemorySink>>>>) {
+    let built = Arc::new(Mutex::new(Vec::new()));
+    let factory_built = built.clone();
+    let factory: SinkFactory = Box::new(move |sinks, labels| {
+        let sink = Arc::new(MemorySink {
+            endpoints: sinks.iter().map(|sink| sink.endpoint.clone()).collect(),
+            labels,
+            logs: Mutex::new(Vec::new()),
+            flushes: AtomicUsize::new(0),
+        });
+        factory_built.lock().unwrap().push(sink.clone());
+        Ok(sink as Arc<dyn LogSink>)
+    });
+    (factory, built)
+}
+
+fn key(service_id: &str) -> ServiceKey {
+    ServiceKey::new(
+        "shop".to_string(),
+        "production".to_string(),
+        service_id.to_string(),
+    )
+}
+
+fn topology(service_ids: &[&str]) -> Topology {
+    let mut topology = Topology::default();
+    for service_id in service_ids {
+        topology.services.insert(
+            key(service_id),
+            DiscoveredService {
+                key: key(service_id),
+                project_name: "shop".to_string(),
+                region: Region {
+                    provider: "aws".to_string(),
+                    name: "Tokyo".to_string(),
+                    id: "aws-tokyo-1".to_string(),
+                },
+                environment_name: "production".to_string(),
+                service_name: service_id.to_string(),
+                latest_deployment: Deployment {
+                    id: None,
+                    plan_type: None,
+                    plan_meta: None,
+                    status: Some("RUNNING".to_string()),
+                },
+            },
+        );
+    }
+    topology
+}
+
+// Topologies of the default account
+fn topologies(topology: Topology) -> AccountTopologies {
+    AccountTopologies {
+        accounts: [("default".to_string(), Arc::new(topology))].into(),
+        ..AccountTopologies::default()
+    }
+}
+
+// Helper function to build a config around the given sink endpoint and extra
+// TOML for the account, whose API is the mock server
+fn config(server: &MockServer, endpoint: &str, extra: &str) -> Config {
+    let text = format!(
+        r#"
+        [[accounts]]
+        api_key = "secret"
+        endpoint = "{zeabur}"
+        {extra}
+
+        [polling]
+        min_interval = "50ms"
+        max_interval = "50ms"
+
+        [[sinks]]
+        name = "main"
+        type = "otlp_http"
+        endpoint = "{endpoint}"
+        "#,
+        zeabur = server.uri(),
+    );
+    Config::parse(&text, ConfigFormat::Toml).unwrap()
+}
+
+async fn mock_zeabur(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(body_string_contains("QueryServiceRuntimeLogs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "runtimeLogs": [
+                { "timestamp": "2024-05-01T12:00:00Z", "zeaburUID": "uid", "message": "hello" }
+            ] }
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_collection_polls_selected_services_into_their_sinks() {
+    let server = MockServer::start().await;
+    let (factory, built) = memory_sinks();
+    mock_zeabur(&server).await;
+    let mut collection = Collection::new(
+        config(
+            &server,
+            "http://a",
+            "[accounts.exclude]\nservices = [\"worker\"]",
+        ),
+        None,
+        factory,
+    )
+    .unwrap();
+
+    let report = collection.sync(&topologies(topology(&["api", "worker"])));
+    assert_eq!(report.added, vec![key("api")]);
+    assert!(collection.is_running(&key("api")));
+    assert!(!collection.is_running(&key("worker")));
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let built = built.lock().unwrap();
+    assert_eq!(built.len(), 1);
+    assert_eq!(built[0].labels["service_name"], "api");
+    // Later polls see the same line and drop it as a duplicate
+    let logs = built[0].logs.lock().unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].message, "hello");
+}
+
+#[tokio::test]
+async fn test_lines_are_classified_with_the_severity_settings_of_their_service() {
+    let server = MockServer::start().await;
+    let (factory, built) = memory_sinks();
+    mock_zeabur(&server).await;
+    let extra = "[[severity.overrides]]\nselect = { services = [\"api\"] }\ndefault = \"info\"";
+    let mut collection =
+        Collection::new(config(&server, "http://a", extra), None, factory).unwrap();
+    collection.sync(&topologies(topology(&["api", "worker"])));
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let built = built.lock().unwrap();
+    let severity_of = |service_id: &str| {
+        let sink = built
+            .iter()
+            .find(|sink| sink.labels["service_id"] == service_id)
+            .unwrap();
+        let logs = sink.logs.lock().unwrap();
+        logs[0].severity
+    };
+    assert_eq!(severity_of("api"), LogSeverity::Info);
+    assert_eq!(severity_of("worker"), LogSeverity::Unspecified);
+}
+
+#[tokio::test]
+async fn test_reload_only_touches_affected_services() {
+    let server = MockServer::start().await;
+    let (factory, built) = memory_sinks();
+    mock_zeabur(&server).await;
+    let mut collection = Collection::new(config(&server, "http://a", ""), None, factory).unwrap();
+    let topology = topologies(topology(&["api", "worker"]));
+    collection.sync(&topology);
+    let api_collector = collection.collector(&key("api")).unwrap();
+    let api_sink = collection.sink(&key("api")).unwrap();
+
+    // Excluding a service stops it and leaves the other one alone
+    let report = collection
+        .reload(
+            config(
+                &server,
+                "http://a",
+                "[accounts.exclude]\nservices = [\"worker\"]",
+            ),
+            &topology,
+        )
+        .unwrap();
+    assert_eq!(report.retired, vec![key("worker")]);
+    assert!(report.updated.is_empty());
+    assert!(!collection.is_running(&key("worker")));
+    assert!(Arc::ptr_eq(
+        &collection.collector(&key("api")).unwrap(),
+        &api_collector
+    ));
+    assert!(Arc::ptr_eq(
+        &collection.sink(&key("api")).unwrap(),
+        &api_sink
+    ));
+
+    // Changing the sink rebuilds it, the collector and its cursor are kept
+    let report = collection
+        .reload(
+            config(
+                &server,
+                "http://b",
+                "[accounts.exclude]\nservices = [\"worker\"]",
+            ),
+            &topology,
+        )
+        .unwrap();
+    assert_eq!(report.updated, vec![key("api")]);
+    assert!(Arc::ptr_eq(
+        &collection.collector(&key("api")).unwrap(),
+        &api_collector
+    ));
+    assert!(!Arc::ptr_eq(
+        &collection.sink(&key("api")).unwrap(),
+        &api_sink
+    ));
+    assert_eq!(
+        built.lock().unwrap().last().unwrap().endpoints,
+        vec![Some("http://b".to_string())]
+    );
+
+    // Including the service again starts a fresh collector for it
+    let report = collection
+        .reload(config(&server, "http://b", ""), &topology)
+        .unwrap();
+    assert_eq!(report.added, vec![key("worker")]);
+    assert!(collection.is_running(&key("worker")));
+    assert!(Arc::ptr_eq(
+        &collection.collector(&key("api")).unwrap(),
+        &api_collector
+    ));
+}
+
+#[tokio::test]
+async fn test_reload_rebuilds_sinks_whose_parser_changed() {
+    let server = MockServer::start().await;
+    let (factory, built) = memory_sinks();
+    Mock::given(method("POST"))
+        .and(body_string_contains("QueryServiceRuntimeLogs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "runtimeLogs": [
+                { "timestamp": "2024-05-01T12:00:00Z", "zeaburUID": "uid", "message": "user=alice msg=hello" }
+            ] }
+        })))
+        .mount(&server)
+        .await;
+    let mut collection = Collection::new(config(&server, "http://a", ""), None, factory).unwrap();
+    let topology = topologies(topology(&["api"]));
+    collection.sync(&topology);
+    let api_sink = collection.sink(&key("api")).unwrap();
+
+    let report = collection
+        .reload(
+            config(&server, "http://a", "[[parsers]]\nformat = \"logfmt\""),
+            &topology,
+        )
+        .unwrap();
+    assert_eq!(report.updated, vec![key("api")]);
+    assert!(!Arc::ptr_eq(
+        &collection.sink(&key("api")).unwrap(),
+        &api_sink
+    ));
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let built = built.lock().unwrap();
+    let logs = built.last().unwrap().logs.lock().unwrap();
+    assert!(!logs.is_empty());
+    assert_eq!(logs[0].attributes["user"], "alice".into());
+}
+
+#[tokio::test]
+async fn test_reload_rebuilds_sinks_whose_multiline_rule_changed() {
+    let server = MockServer::start().await;
+    let (factory, _built) = memory_sinks();
+    mock_zeabur(&server).await;
+    let mut collection = Collection::new(config(&server, "http://a", ""), None, factory).unwrap();
+    let topology = topologies(topology(&["api"]));
+    collection.sync(&topology);
+    let api_sink = collection.sink(&key("api")).unwrap();
+
+    let report = collection
+        .reload(
+            config(&server, "http://a", "[[multiline]]\npreset = \"node\""),
+            &topology,
+        )
+        .unwrap();
+    assert_eq!(report.updated, vec![key("api")]);
+    let multiline_sink = collection.sink(&key("api")).unwrap();
+    assert!(!Arc::ptr_eq(&multiline_sink, &api_sink));
+
+    // Only the bounds changing is enough as well
+    let report = collection
+        .reload(
+            config(
+                &server,
+                "http://a",
+                "[[multiline]]\npreset = \"node\"\nmax_lines = 10",
+            ),
+            &topology,
+        )
+        .unwrap();
+    assert_eq!(report.updated, vec![key("api")]);
+    assert!(!Arc::ptr_eq(
+        &collection.sink(&key("api")).unwrap(),
+        &multiline_sink
+    ));
+}
+
+#[tokio::test]
+async fn test_reload_rebuilds_sinks_whose_processors_changed() {
+    let server = MockServer::start().await;
+    let (factory, built) = memory_sinks();
+    mock_zeabur(&server).await;
+    let mut collection = Collection::new(config(&server, "http://a", ""), None, factory).unwrap();
+    let topology = topologies(topology(&["api"]));
+    collection.sync(&topology);
+    let api_sink = collection.sink(&key("api")).unwrap();
+
+    let report = collection
+        .reload(
+            config(
+                &server,
+                "http://a",
+                "[[processors]]\ntype = \"drop\"\nmatch = { message = \"^hello$\" }",
+            ),
+            &topology,
+        )
+        .unwrap();
+    assert_eq!(report.updated, vec![key("api")]);
+    assert!(!Arc::ptr_eq(
+        &collection.sink(&key("api")).unwrap(),
+        &api_sink
+    ));
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let built = built.lock().unwrap();
+    assert!(built.last().unwrap().logs.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_shutdown_stops_pollers_and_flushes_sinks() {
+    let server = MockServer::start().await;
+    let (factory, built) = memory_sinks();
+    mock_zeabur(&server).await;
+    let mut collection = Collection::new(config(&server, "http://a", ""), None, factory).unwrap();
+    collection.sync(&topologies(topology(&["api", "worker"])));
+
+    let report = collection.shutdown(Duration::from_secs(5)).await;
+
+    assert!(report.is_clean());
+    assert_eq!(report.stopped, vec![key("api"), key("worker")]);
+    assert!(!collection.is_running(&key("api")));
+    let built = built.lock().unwrap();
+    assert_eq!(built.len(), 2);
+    assert!(built
+        .iter()
+        .all(|sink| sink.flushes.load(Ordering::SeqCst) == 1));
+}
+
+#[tokio::test]
+async fn test_reload_signals_fire_when_the_config_file_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("zeabur-ops.toml");
+    std::fs::write(&path, "# before").unwrap();
+
+    let mut reloads = ReloadSignals::new(Some(&path))
+        .unwrap()
+        .with_debounce(Duration::from_millis(50));
+
+    // Unrelated files in the same directory are ignored
+    std::fs::write(dir.path().join("other.toml"), "# other").unwrap();
+    assert!(
+        tokio::time::timeout(Duration::from_millis(300), reloads.recv())
+            .await
+            .is_err()
+    );
+
+    std::fs::write(&path, "# after").unwrap();
+    tokio::time::timeout(Duration::from_secs(5), reloads.recv())
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+// Helper function to build a config with two accounts on the mock server
+fn two_accounts(server: &MockServer, team_b_key: &str) -> Config {
+    let text = format!(
+        r#"
+        [[accounts]]
+        name = "team-a"
+        api_key = "a"
+        endpoint = "{zeabur}"
+
+        [[accounts]]
+        name = "team-b"
+        api_key = "{team_b_key}"
+        endpoint = "{zeabur}"
+
+        [[sinks]]
+        name = "main"
+        type = "otlp_http"
+        endpoint = "http://a"
+        "#,
+        zeabur = server.uri(),
+    );
+    Config::parse(&text, ConfigFormat::Toml).unwrap()
+}
+
+#[tokio::test]
+async fn test_accounts_are_collected_and_replaced_independently() {
+    let server = MockServer::start().await;
+    mock_zeabur(&server).await;
+    let (factory, built) = memory_sinks();
+    let mut collection = Collection::new(two_accounts(&server, "b"), None, factory).unwrap();
+
+    // A service visible to both accounts is collected with the first one
+    let mut snapshot = AccountTopologies {
+        accounts: [
+            ("team-a".to_string(), Arc::new(topology(&["api"]))),
+            ("team-b".to_string(), Arc::new(topology(&["api", "worker"]))),
+        ]
+        .into(),
+        ..AccountTopologies::default()
+    };
+    let report = collection.sync(&snapshot);
+    assert_eq!(report.added, vec![key("api"), key("worker")]);
+    let account_of = |service_id: &str| {
+        built
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|sink| sink.labels["service_id"] == service_id)
+            .map(|sink| sink.labels["account"].clone())
+            .unwrap()
+    };
+    assert_eq!(account_of("api"), "team-a");
+    assert_eq!(account_of("worker"), "team-b");
+
+    // A new key for team-b only replaces the collectors of team-b
+    let api_collector = collection.collector(&key("api")).unwrap();
+    let worker_collector = collection.collector(&key("worker")).unwrap();
+    let report = collection
+        .reload(two_accounts(&server, "rotated"), &snapshot)
+        .unwrap();
+    assert_eq!(report.updated, vec![key("worker")]);
+    assert!(Arc::ptr_eq(
+        &collection.collector(&key("api")).unwrap(),
+        &api_collector
+    ));
+    assert!(!Arc::ptr_eq(
+        &collection.collector(&key("worker")).unwrap(),
+        &worker_collector
+    ));
+
+    // When team-a stops, its services move over to team-b
+    snapshot.accounts.remove("team-a");
+    snapshot
+        .failed
+        .insert("team-a".to_string(), "Unauthorized".to_string());
+    let report = collection.sync(&snapshot);
+    assert_eq!(report.updated, vec![key("api")]);
+    assert!(report.retired.is_empty());
+    assert!(collection.is_running(&key("api")));
+    assert_eq!(account_of("api"), "team-b");
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use zeabur_ops::log::collector_registry::{CollectorRegistry, ServiceTarget};
+use zeabur_ops::zeabur::service_key::ServiceKey;
+
+// Helper function to build a target with a single label
+fn target(service_id: &str, service_name: &str) -> ServiceTarget {
+    let mut labels = HashMap::new();
+    labels.insert("service_name".to_string(), service_name.to_string());
+    ServiceTarget {
+        key: ServiceKey::new(
+            "project".to_string(),
+            "environment".to_string(),
+            service_id.to_string(),
+        ),
+        labels,
+    }
+}
+
+// Collector stand-in which remembers the order it was created in
+struct FakeCollector {
+    generation: usize,
+}
+
+#[test]
+fn test_sync_keeps_collectors_across_ticks() {
+    let created = Arc::new(AtomicUsize::new(0));
+    let mut registry: CollectorRegistry<FakeCollector, ()> = CollectorRegistry::new();
+
+    let make_collector = |_: &ServiceTarget| {
+        Ok(FakeCollector {
+            generation: created.fetch_add(1, Ordering::SeqCst),
+        })
+    };
+
+    let report = registry.sync(
+        vec![target("a", "api"), target("b", "worker")],
+        make_collector,
+        |_| Ok(()),
+    );
+    assert_eq!(report.added.len(), 2);
+    assert!(report.retired.is_empty());
+
+    // A second tick with the same discovery result must not rebuild anything
+    let report = registry.sync(
+        vec![target("a", "api"), target("b", "worker")],
+        make_collector,
+        |_| Ok(()),
+    );
+    assert!(report.added.is_empty());
+    assert!(report.updated.is_empty());
+    assert_eq!(created.load(Ordering::SeqCst), 2);
+    assert_eq!(registry.len(), 2);
+}
+
+#[test]
+fn test_sync_adds_and_retires_services() {
+    let mut registry: CollectorRegistry<(), ()> = CollectorRegistry::new();
+    registry.sync(
+        vec![target("a", "api"), target("b", "worker")],
+        |_| Ok(()),
+        |_| Ok(()),
+    );
+
+    let report = registry.sync(
+        vec![target("b", "worker"), target("c", "cron")],
+        |_| Ok(()),
+        |_| Ok(()),
+    );
+
+    assert_eq!(report.added, vec![target("c", "cron").key]);
+    assert_eq!(report.retired, vec![target("a", "api").key]);
+    assert!(registry.get(&target("a", "api").key).is_none());
+    assert!(registry.get(&target("c", "cron").key).is_some());
+}
+
+#[test]
+fn test_sync_rebuilds_sink_but_keeps_collector_when_labels_change() {
+    let mut registry: CollectorRegistry<FakeCollector, String> = CollectorRegistry::new();
+    registry.sync(
+        vec![target("a", "api")],
+        |_| Ok(FakeCollector { generation: 0 }),
+        |t| Ok(t.labels["service_name"].clone()),
+    );
+
+    let report = registry.sync(
+        vec![target("a", "api-renamed")],
+        |_| Ok(FakeCollector { generation: 1 }),
+        |t| Ok(t.labels["service_name"].clone()),
+    );
+
+    assert_eq!(report.updated, vec![target("a", "api").key]);
+    let entry = registry.get(&target("a", "api").key).unwrap();
+    assert_eq!(entry.collector.generation, 0);
+    assert_eq!(entry.sink, "api-renamed");
+}
+
+#[test]
+fn test_sync_reports_failed_targets_and_retries_them() {
+    let mut registry: CollectorRegistry<(), ()> = CollectorRegistry::new();
+
+    let report = registry.sync(
+        vec![target("a", "api")],
+        |_| Ok(()),
+        |_| Err(anyhow::anyhow!("exporter unavailable")),
+    );
+    assert_eq!(report.failed.len(), 1);
+    assert!(registry.is_empty());
+
+    let report = registry.sync(vec![target("a", "api")], |_| Ok(()), |_| Ok(()));
+    assert_eq!(report.added, vec![target("a", "api").key]);
+}
+
+#[test]
+fn test_rebuild_sinks_keeps_collectors() {
+    let mut registry: CollectorRegistry<FakeCollector, String> = CollectorRegistry::new();
+    registry.sync(
+        vec![target("a", "api"), target("b", "worker")],
+        |_| Ok(FakeCollector { generation: 0 }),
+        |_| Ok("old".to_string()),
+    );
+
+    let report = registry.rebuild_sinks(
+        &[target("a", "api").key, target("missing", "gone").key],
+        |_| Ok("new".to_string()),
+    );
+
+    assert_eq!(report.updated, vec![target("a", "api").key]);
+    let rebuilt = registry.get(&target("a", "api").key).unwrap();
+    assert_eq!(rebuilt.sink, "new");
+    assert_eq!(rebuilt.collector.generation, 0);
+    assert_eq!(registry.get(&target("b", "worker").key).unwrap().sink, "old");
+}
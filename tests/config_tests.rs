@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::time::Duration;
+use zeabur_ops::config::error::ConfigError;
+use zeabur_ops::config::{Config, ConfigFormat};
+use zeabur_ops::log::log_entry::LogSeverity;
+use zeabur_ops::log::multiline::{MultilinePreset, MultilineRule};
+use zeabur_ops::log::parse::{ParseFormat, ParseTarget, PromotedFields};
+use zeabur_ops::log::processor::BuiltinProcessor;
+use zeabur_ops::log::zeabur_log_collector::CollectorMode;
+use zeabur_ops::zeabur::get_services_of_project::Deployment;
+use zeabur_ops::zeabur::list_projects::Region;
+use zeabur_ops::zeabur::rate_limit::RateLimits;
+use zeabur_ops::zeabur::service_key::ServiceKey;
+use zeabur_ops::zeabur::topology::DiscoveredService;
+
+const EXAMPLE: &str = include_str!("../zeabur-ops.example.toml");
+
+// Helper function to parse with a fixed set of environment variables
+fn parse(text: &str, format: ConfigFormat, vars: &[(&str, &str)]) -> Result<Config, ConfigError> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    Config::parse_with_env(text, format, move |name| vars.get(name).cloned())
+}
+
+// Helper function to build a discovered service from names, IDs are the names
+// with an "-id" suffix
+fn service(project: &str, environment: &str, service: &str) -> DiscoveredService {
+    DiscoveredService {
+        key: ServiceKey::new(
+            format!("{}-id", project),
+            format!("{}-id", environment),
+            format!("{}-id", service),
+        ),
+        project_name: project.to_string(),
+        region: Region {
+            provider: "aws".to_string(),
+            name: "Tokyo".to_string(),
+            id: "aws-tokyo-1".to_string(),
+        },
+        environment_name: environment.to_string(),
+        service_name: service.to_string(),
+        latest_deployment: Deployment {
+            id: None,
+            plan_type: None,
+            plan_meta: None,
+            status: Some("RUNNING".to_string()),
+        },
+    }
+}
+
+#[test]
+fn test_example_config_is_valid() {
+    let config = parse(
+        EXAMPLE,
+        ConfigFormat::Toml,
+        &[
+            ("ZEABUR_API_KEY", "secret"),
+            ("ZEABUR_DATA_API_KEY", "data-secret"),
+            ("GRAFANA_OTLP_TOKEN", "token"),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(config.accounts[0].api_key, "secret");
+    assert_eq!(config.accounts[1].name, "data");
+    assert_eq!(
+        config.checkpoint_store.as_deref(),
+        Some("sqlite:/data/cursors.db")
+    );
+    assert_eq!(config.polling.discovery_interval, Duration::from_secs(60));
+    assert_eq!(
+        config.backfill.since,
+        Some("2024-05-01T00:00:00Z".parse().unwrap())
+    );
+    assert_eq!(config.sinks[1].headers["Authorization"], "Basic token");
+
+    let production = config
+        .pipeline_for(&service("shop-a", "production", "api"))
+        .unwrap();
+    assert_eq!(production.name, "production");
+    assert_eq!(config.sinks_of(production).len(), 2);
+    assert_eq!(
+        config.poll_settings(production).min_interval,
+        Duration::from_secs(1)
+    );
+    assert_eq!(
+        production.render_labels("default", &service("shop-a", "production", "api"))
+            ["deployment.environment"],
+        "production"
+    );
+
+    let other = config
+        .pipeline_for(&service("shop-a", "staging", "api"))
+        .unwrap();
+    assert_eq!(other.name, "everything-else");
+    assert_eq!(
+        config.poll_settings(other).min_interval,
+        Duration::from_secs(2)
+    );
+}
+
+#[test]
+fn test_account_filters_match_names_and_ids() {
+    let config = parse(
+        EXAMPLE,
+        ConfigFormat::Toml,
+        &[
+            ("ZEABUR_API_KEY", "secret"),
+            ("ZEABUR_DATA_API_KEY", "data-secret"),
+        ],
+    )
+    .unwrap();
+    let account = &config.accounts[0];
+
+    assert!(account.accepts(&service("shop-a", "production", "api")));
+    assert!(!account.accepts(&service("blog", "production", "api")));
+    assert!(!account.accepts(&service("shop-a", "preview-42", "api")));
+    assert!(!account.accepts(&service("shop-a", "production", "redis")));
+    // IDs match as well as names
+    assert!(!account.accepts(&service("shop-a", "production", "postgresql")));
+    let mut by_id = service("shop-a", "production", "api");
+    by_id.key.service_id = "redis".to_string();
+    assert!(!account.accepts(&by_id));
+}
+
+#[test]
+fn test_yaml_config_defaults_to_a_single_pipeline() {
+    let config = parse(
+        "accounts:\n  - api_key: ${KEY:-fallback}\npolling:\n  max_interval: 1m\n",
+        ConfigFormat::Yaml,
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(config.accounts[0].name, "default");
+    assert_eq!(config.accounts[0].api_key, "fallback");
+    assert_eq!(config.polling.max_interval, Duration::from_secs(60));
+    assert_eq!(config.sinks.len(), 1);
+    assert_eq!(config.pipelines.len(), 1);
+    assert!(config
+        .pipeline_for(&service("any", "production", "api"))
+        .is_some());
+}
+
+#[test]
+fn test_backfill_section_sets_the_collector_mode() {
+    let config = parse(
+        "[[accounts]]\napi_key = \"secret\"\n[backfill]\nsince = \"2024-05-01T00:00:00Z\"\npages = 12\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        config.collector_mode(),
+        CollectorMode::Backfill {
+            since: Some("2024-05-01T00:00:00Z".parse().unwrap()),
+            page_budget: 12,
+        }
+    );
+
+    let error = parse(
+        "[[accounts]]\napi_key = \"secret\"\n[backfill]\npages = 0\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid config:\n  - backfill.pages must be at least 1"
+    );
+
+    let config = parse(
+        "[[accounts]]\napi_key = \"secret\"\n[backfill]\nenabled = false\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap();
+    assert_eq!(config.collector_mode(), CollectorMode::Tail);
+}
+
+#[test]
+fn test_errors_point_at_the_offending_field() {
+    let error = parse(
+        "[[accounts]]\napi_key = \"${MISSING}\"\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid config at accounts[0].api_key: environment variable MISSING is not set"
+    );
+
+    let error = parse(
+        "[[accounts]]\napi_key = \"secret\"\n[accounts.include]\nservices = [\"api\", \"[\"]\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("Invalid config at accounts[0].include.services[1]: invalid glob pattern"));
+
+    let error = parse(
+        "[[accounts]]\napi_key = \"secret\"\n[[pipelines]]\nname = \"p\"\nlabels = { team = \"{owner}\" }\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("unknown placeholder {owner}"));
+
+    let error = parse(
+        "[[accounts]]\napi_key = \"secret\"\npoll = 1\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("unknown field `poll`"));
+}
+
+#[test]
+fn test_validation_reports_every_problem() {
+    let error = parse(
+        r#"
+        [[accounts]]
+        api_key = ""
+
+        [polling]
+        min_interval = "10s"
+        max_interval = "5s"
+
+        [[sinks]]
+        name = "vector"
+        type = "otlp_http"
+
+        [[pipelines]]
+        name = "main"
+        sinks = ["loki"]
+        "#,
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap_err();
+
+    let ConfigError::Invalid(problems) = error else {
+        panic!("expected a validation error, got {:?}", error);
+    };
+    assert_eq!(
+        problems,
+        vec![
+            "accounts[0].api_key must not be empty",
+            "polling: min_interval (10s) must not be greater than max_interval (5s)",
+            "pipelines[0] (main) refers to unknown sink \"loki\"",
+            "pipelines[0] (main): min_interval (10s) must not be greater than max_interval (5s)",
+        ]
+    );
+}
+
+#[test]
+fn test_accounts_have_their_own_rate_limits() {
+    let config = parse(
+        r#"
+        accounts:
+          - name: team-a
+            api_key: a
+            rate_limits:
+              logs:
+                requests_per_second: 2.5
+          - name: team-b
+            api_key: b
+        "#,
+        ConfigFormat::Yaml,
+        &[],
+    )
+    .unwrap();
+
+    let defaults = RateLimits::default();
+    let team_a = config.accounts[0].rate_limits.apply(RateLimits::default());
+    assert_eq!(team_a.logs.requests_per_second, 2.5);
+    assert_eq!(team_a.logs.burst, defaults.logs.burst);
+    assert_eq!(team_a.discovery, defaults.discovery);
+    assert_eq!(
+        config.accounts[1].rate_limits.apply(RateLimits::default()),
+        defaults
+    );
+    assert!(!config.accounts[0].same_client(&config.accounts[1]));
+
+    let error = parse(
+        r#"
+        accounts:
+          - name: team-a
+            api_key: a
+            rate_limits:
+              discovery:
+                requests_per_second: 0
+                max_concurrency: 0
+              logs:
+                requests_per_second: -2.5
+          - name: team-a
+            api_key: b
+        "#,
+        ConfigFormat::Yaml,
+        &[],
+    )
+    .unwrap_err();
+    let ConfigError::Invalid(problems) = error else {
+        panic!("expected a validation error, got {:?}", error);
+    };
+    assert_eq!(
+        problems,
+        vec![
+            "accounts contains \"team-a\" more than once",
+            "accounts[0].rate_limits.discovery.requests_per_second must be a finite number greater than zero",
+            "accounts[0].rate_limits.discovery.max_concurrency must be at least 1",
+            "accounts[0].rate_limits.logs.requests_per_second must be a finite number greater than zero",
+        ]
+    );
+}
+
+#[test]
+fn test_config_check_subcommand() {
+    let dir = tempfile::tempdir().unwrap();
+    let valid = dir.path().join("valid.yaml");
+    std::fs::write(&valid, "accounts:\n  - api_key: secret\n").unwrap();
+    let invalid = dir.path().join("invalid.toml");
+    std::fs::write(&invalid, "[[accounts]]\napi_key = \"\"\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zeabur-ops"))
+        .args(["--config", valid.to_str().unwrap(), "config", "check"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Configuration is valid"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_zeabur-ops"))
+        .args(["config", "check", "--config", invalid.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("api_key must not be empty"));
+}
+
+#[test]
+fn test_severity_overrides_apply_to_the_services_they_select() {
+    let config = parse(
+        r#"
+        [[accounts]]
+        api_key = "secret"
+
+        [[severity.overrides]]
+        select = { services = ["legacy-*"] }
+        detect = false
+        default = "warning"
+        rules = [{ pattern = "^E\\d{4} ", level = "error" }]
+        "#,
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap();
+
+    let legacy = config.severity_classifier(&service("shop", "production", "legacy-api"));
+    assert_eq!(legacy.classify("E1234 disk full"), LogSeverity::Error);
+    assert_eq!(legacy.classify("level=error boom"), LogSeverity::Warn);
+
+    let api = config.severity_classifier(&service("shop", "production", "api"));
+    assert_eq!(api.classify("E1234 disk full"), LogSeverity::Unspecified);
+    assert_eq!(api.classify("level=error boom"), LogSeverity::Error);
+
+    let error = parse(
+        "[[accounts]]\napi_key = \"secret\"\n[[severity.overrides]]\nrules = [{ pattern = \"(\", level = \"error\" }]\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap_err();
+    assert!(error.to_string().starts_with(
+        "Invalid config at severity.overrides[0].rules[0].pattern: invalid regular expression"
+    ));
+}
+
+#[test]
+fn test_parsers_apply_to_the_services_they_select() {
+    let config = parse(
+        r#"
+        [[accounts]]
+        api_key = "secret"
+
+        [[parsers]]
+        select = { services = ["api"] }
+        format = "json"
+        into = "both"
+        promote = { message = ["event"] }
+        "#,
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap();
+
+    let parser = config
+        .body_parser(&service("shop", "production", "api"))
+        .unwrap();
+    assert_eq!(parser.format, ParseFormat::Json);
+    assert_eq!(parser.target, ParseTarget::Both);
+    assert_eq!(parser.promote.message, vec!["event".to_string()]);
+    assert_eq!(parser.promote.level, PromotedFields::default().level);
+    assert!(config
+        .body_parser(&service("shop", "production", "worker"))
+        .is_none());
+}
+
+#[test]
+fn test_multiline_rules_apply_to_the_services_they_select() {
+    let config = parse(
+        r#"
+        [[accounts]]
+        api_key = "secret"
+
+        [[multiline]]
+        select = { services = ["api"] }
+        preset = "java"
+        max_lines = 100
+        flush_timeout = "10s"
+        "#,
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap();
+
+    let rule = config
+        .multiline_rule(&service("shop", "production", "api"))
+        .unwrap();
+    assert_eq!(
+        rule.continuation,
+        Some(MultilinePreset::Java.continuation())
+    );
+    assert_eq!(rule.max_lines, 100);
+    assert_eq!(rule.max_bytes, MultilineRule::default().max_bytes);
+    assert_eq!(rule.flush_timeout, Duration::from_secs(10));
+    assert!(config
+        .multiline_rule(&service("shop", "production", "worker"))
+        .is_none());
+
+    let error = parse(
+        "[[accounts]]\napi_key = \"secret\"\n[[multiline]]\nmax_lines = 0\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid config:\n  - multiline[0] needs a preset, a start or a continuation pattern\n  - multiline[0].max_lines must be at least 1"
+    );
+}
+
+#[test]
+fn test_processors_apply_to_the_services_they_select() {
+    let config = parse(
+        r#"
+        [[accounts]]
+        name = "shop"
+        api_key = "secret"
+
+        [[sinks]]
+        name = "main"
+        type = "otlp_http"
+
+        [[sinks]]
+        name = "alerts"
+        type = "otlp_http"
+
+        [[processors]]
+        type = "drop"
+        match = { message = "^GET /health " }
+
+        [[processors]]
+        type = "enrich"
+        select = { services = ["api"] }
+        attributes = { owner = "{account}-{service_name}" }
+
+        [[processors]]
+        type = "route"
+        select = { services = ["api"] }
+        match = { severity = "error" }
+        sinks = ["alerts"]
+        "#,
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap();
+
+    let api = config.processors_for("shop", &service("shop", "production", "api"));
+    assert_eq!(api.len(), 3);
+    let BuiltinProcessor::Enrich(enrich) = &api[1] else {
+        panic!("expected an enrich processor, got {:?}", api[1]);
+    };
+    assert_eq!(enrich.attributes["owner"], "shop-api");
+    let routes = config.route_sinks(&api);
+    assert_eq!(routes.len(), 1);
+    assert_eq!(routes[0].name, "alerts");
+
+    let worker = config.processors_for("shop", &service("shop", "production", "worker"));
+    assert_eq!(worker.len(), 1);
+    assert!(config.route_sinks(&worker).is_empty());
+
+    let error = parse(
+        "[[accounts]]\napi_key = \"secret\"\n[[processors]]\ntype = \"drop\"\n[[processors]]\ntype = \"route\"\nsinks = [\"archive\"]\n",
+        ConfigFormat::Toml,
+        &[],
+    )
+    .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Invalid config:\n  - processors[0] (drop) needs a match\n  - processors[1] (route) refers to unknown sink \"archive\""
+    );
+
+    // Options a processor needs, or does not take, are rejected when parsing
+    let error = |processor: &str| {
+        parse(
+            &format!(
+                "[[accounts]]\napi_key = \"secret\"\n[[processors]]\n{}",
+                processor
+            ),
+            ConfigFormat::Toml,
+            &[],
+        )
+        .unwrap_err()
+        .to_string()
+    };
+    assert_eq!(
+        error("type = \"map\""),
+        "Invalid config at processors[0]: missing field `pattern`"
+    );
+    assert!(
+        error("type = \"drop\"\nmatch = { message = \"x\" }\ncopy = true")
+            .contains("unknown field `copy`")
+    );
+}
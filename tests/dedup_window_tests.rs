@@ -0,0 +1,31 @@
+use chrono::{TimeZone, Utc};
+use zeabur_ops::log::dedup_window::{DedupKey, DedupWindow};
+
+#[test]
+fn test_lines_sharing_a_timestamp_are_distinct() {
+    let timestamp = Utc.timestamp_millis_opt(1_700_000_000_123).unwrap();
+    let mut window = DedupWindow::new(16);
+
+    assert!(window.insert(DedupKey::new(timestamp, "uid-1", "GET /health")));
+    assert!(window.insert(DedupKey::new(timestamp, "uid-1", "GET /metrics")));
+    assert!(window.insert(DedupKey::new(timestamp, "uid-2", "GET /health")));
+
+    // The exact same line is a real repeat
+    assert!(!window.insert(DedupKey::new(timestamp, "uid-1", "GET /health")));
+    assert_eq!(window.len(), 3);
+}
+
+#[test]
+fn test_window_evicts_oldest_keys_beyond_capacity() {
+    let mut window = DedupWindow::new(2);
+    let key = |millis: i64| DedupKey::new(Utc.timestamp_millis_opt(millis).unwrap(), "uid", "line");
+
+    window.insert(key(1));
+    window.insert(key(2));
+    window.insert(key(3));
+
+    assert_eq!(window.len(), 2);
+    assert!(!window.contains(&key(1)));
+    assert!(window.contains(&key(2)));
+    assert!(window.contains(&key(3)));
+}
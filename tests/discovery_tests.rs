@@ -0,0 +1,231 @@
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::matchers::{body_string_contains, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zeabur_ops::daemon::discovery::{
+    diff_topology, AccountDiscovery, AccountTopologies, DiscoveryTask, TopologyEvent,
+};
+use zeabur_ops::zeabur::client::ZeaburClient;
+use zeabur_ops::zeabur::get_services_of_project::Deployment;
+use zeabur_ops::zeabur::list_projects::Region;
+use zeabur_ops::zeabur::retry::RetryPolicy;
+use zeabur_ops::zeabur::service_key::ServiceKey;
+use zeabur_ops::zeabur::topology::{DiscoveredService, Topology};
+
+fn key(service_id: &str) -> ServiceKey {
+    ServiceKey::new(
+        "shop".to_string(),
+        "production".to_string(),
+        service_id.to_string(),
+    )
+}
+
+// Helper function to build a discovered service with the given deployment status
+fn discovered(service_id: &str, status: &str) -> DiscoveredService {
+    DiscoveredService {
+        key: key(service_id),
+        project_name: "shop-name".to_string(),
+        region: Region {
+            provider: "aws".to_string(),
+            name: "Tokyo".to_string(),
+            id: "aws-tokyo-1".to_string(),
+        },
+        environment_name: "Production".to_string(),
+        service_name: format!("{}-name", service_id),
+        latest_deployment: Deployment {
+            id: None,
+            plan_type: None,
+            plan_meta: None,
+            status: Some(status.to_string()),
+        },
+    }
+}
+
+fn topology(services: &[DiscoveredService]) -> Topology {
+    Topology {
+        services: services
+            .iter()
+            .map(|service| (service.key.clone(), service.clone()))
+            .collect(),
+        ..Topology::default()
+    }
+}
+
+// Helper function to mount one project with the given services in production
+async fn mount_zeabur(server: &MockServer, services: Value) {
+    server.reset().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("GetProjects"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "projects": { "edges": [{ "node": {
+                "name": "shop-name",
+                "description": "",
+                "iconURL": "",
+                "_id": "shop",
+                "region": { "provider": "aws", "name": "Tokyo", "id": "aws-tokyo-1" },
+                "environments": [{ "_id": "production", "name": "Production" }],
+                "owner": null,
+                "collaborators": []
+            } }] } }
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("GetServicesOfProject"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "data": { "project": { "services": services } } })),
+        )
+        .mount(server)
+        .await;
+}
+
+fn service(id: &str) -> Value {
+    json!({
+        "_id": id,
+        "name": format!("{}-name", id),
+        "latestDeployment": { "status": "RUNNING" },
+    })
+}
+
+#[test]
+fn test_diff_topology_reports_added_removed_and_changed() {
+    let previous = topology(&[
+        discovered("api", "RUNNING"),
+        discovered("worker", "RUNNING"),
+    ]);
+    let current = topology(&[discovered("api", "BUILDING"), discovered("web", "RUNNING")]);
+
+    let events = diff_topology(&previous, &current);
+
+    assert_eq!(
+        events,
+        vec![
+            TopologyEvent::Changed {
+                previous: Box::new(discovered("api", "RUNNING")),
+                current: Box::new(discovered("api", "BUILDING")),
+            },
+            TopologyEvent::Removed(key("worker")),
+            TopologyEvent::Added(Box::new(discovered("web", "RUNNING"))),
+        ]
+    );
+    assert!(diff_topology(&current, &current).is_empty());
+}
+
+#[tokio::test]
+async fn test_discovery_task_publishes_snapshots_and_events() {
+    let server = MockServer::start().await;
+    mount_zeabur(&server, json!([service("api")])).await;
+    let client = ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    let discovery = DiscoveryTask::spawn(client, Duration::from_millis(50));
+    let mut topology = discovery.subscribe_topology();
+    let mut events = discovery.subscribe_events();
+
+    topology.changed().await.unwrap();
+    assert!(topology.borrow().services.contains_key(&key("api")));
+    assert!(
+        matches!(events.recv().await.unwrap(), TopologyEvent::Added(service) if service.key == key("api"))
+    );
+
+    // A failing refresh keeps the previous snapshot instead of dropping services
+    server.reset().await;
+    tokio::time::sleep(Duration::from_millis(120)).await;
+    assert!(topology.borrow().services.contains_key(&key("api")));
+
+    mount_zeabur(&server, json!([service("web")])).await;
+    let mut changes = Vec::new();
+    while changes.len() < 2 {
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        changes.push(event);
+    }
+    assert_eq!(changes[0], TopologyEvent::Removed(key("api")));
+    assert!(matches!(&changes[1], TopologyEvent::Added(service) if service.key == key("web")));
+
+    let snapshot = topology.borrow_and_update().clone();
+    assert_eq!(
+        snapshot.services.keys().collect::<Vec<_>>(),
+        vec![&key("web")]
+    );
+
+    discovery.abort();
+}
+
+#[tokio::test]
+async fn test_account_discovery_isolates_rejected_accounts() {
+    let healthy = MockServer::start().await;
+    mount_zeabur(&healthy, json!([service("api")])).await;
+    let rejected = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&rejected)
+        .await;
+    let client = |server: &MockServer| {
+        ZeaburClient::builder("test-api-key".to_string())
+            .endpoint(server.uri())
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap()
+    };
+
+    // The seed of an account which is not configured anymore is dropped
+    let seed = AccountTopologies {
+        accounts: [("gone".to_string(), Arc::new(Topology::default()))].into(),
+        ..AccountTopologies::default()
+    };
+    let discovery = AccountDiscovery::spawn(
+        [
+            ("healthy".to_string(), client(&healthy)),
+            ("rejected".to_string(), client(&rejected)),
+        ]
+        .into(),
+        Duration::from_millis(50),
+        seed,
+    );
+    let mut topologies = discovery.subscribe_topologies();
+    assert!(topologies.borrow().accounts.is_empty());
+
+    let snapshot = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            topologies.changed().await.unwrap();
+            let snapshot = topologies.borrow_and_update().clone();
+            if snapshot.accounts.contains_key("healthy") && !snapshot.failed.is_empty() {
+                return snapshot;
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    assert!(snapshot.accounts["healthy"]
+        .services
+        .contains_key(&key("api")));
+    assert!(!snapshot.accounts.contains_key("rejected"));
+    assert_eq!(snapshot.failed.keys().collect::<Vec<_>>(), vec!["rejected"]);
+
+    // The healthy account keeps being refreshed
+    mount_zeabur(&healthy, json!([service("web")])).await;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            topologies.changed().await.unwrap();
+            if topologies.borrow_and_update().accounts["healthy"]
+                .services
+                .contains_key(&key("web"))
+            {
+                return;
+            }
+        }
+    })
+    .await
+    .unwrap();
+
+    discovery.abort();
+}
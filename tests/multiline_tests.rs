@@ -0,0 +1,228 @@
+use anyhow::Error;
+use async_trait::async_trait;
+use chrono::Utc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+use zeabur_ops::log::log_entry::{LogEntry, LogSource};
+use zeabur_ops::log::log_sink::LogSink;
+use zeabur_ops::log::multiline::{MultilineCombiner, MultilinePreset, MultilineRule};
+use zeabur_ops::log::severity::LinePattern;
+use zeabur_ops::log::sink::multiline_log_sink::MultilineLogSink;
+
+// Helper function to build lines written by one instance
+fn lines(messages: &[&str]) -> Vec<LogEntry> {
+    messages
+        .iter()
+        .map(|message| LogEntry::new(Utc::now(), *message))
+        .collect()
+}
+
+fn preset(preset: MultilinePreset) -> MultilineRule {
+    MultilineRule {
+        continuation: Some(preset.continuation()),
+        ..MultilineRule::default()
+    }
+}
+
+fn messages(logs: &[LogEntry]) -> Vec<&str> {
+    logs.iter().map(|log| log.message.as_str()).collect()
+}
+
+#[test]
+fn test_presets_combine_stack_traces() {
+    let cases = [
+        (
+            MultilinePreset::Java,
+            vec![
+                "Exception in thread \"main\" java.lang.IllegalStateException: boom",
+                "\tat com.shop.Api.handle(Api.java:42)",
+                "\tat com.shop.Main.main(Main.java:7)",
+                "Caused by: java.io.IOException: closed",
+                "\t... 2 more",
+            ],
+        ),
+        (
+            MultilinePreset::Python,
+            vec![
+                "ERROR:root:checkout failed",
+                "Traceback (most recent call last):",
+                "  File \"/app/main.py\", line 3, in <module>",
+                "    checkout()",
+                "ValueError: invalid literal",
+            ],
+        ),
+        (
+            MultilinePreset::Go,
+            vec![
+                "panic: runtime error: index out of range [3] with length 3",
+                "",
+                "goroutine 1 [running]:",
+                "main.main()",
+                "\t/app/main.go:12 +0x1d",
+                "exit status 2",
+            ],
+        ),
+        (
+            MultilinePreset::Node,
+            vec![
+                "Error: connect ECONNREFUSED 127.0.0.1:5432",
+                "    at TCPConnectWrap.afterConnect (node:net:1555:16)",
+            ],
+        ),
+    ];
+
+    for (name, trace) in cases {
+        let mut combiner = MultilineCombiner::new(preset(name));
+        let mut input = trace.clone();
+        input.push("server listening on :8080");
+        let complete = combiner.push(lines(&input), Instant::now());
+
+        assert_eq!(messages(&complete), vec![trace.join("\n")], "{:?}", name);
+        // The last line waits for possible continuations
+        assert!(combiner.is_pending());
+        assert_eq!(
+            messages(&combiner.flush()),
+            vec!["server listening on :8080"]
+        );
+    }
+}
+
+#[test]
+fn test_start_patterns_begin_entries() {
+    let mut combiner = MultilineCombiner::new(MultilineRule {
+        start: Some(LinePattern::try_from(r"^\d{4}-\d{2}-\d{2} ".to_string()).unwrap()),
+        ..MultilineRule::default()
+    });
+    let complete = combiner.push(
+        lines(&[
+            "2024-05-01 12:00:00 query failed:",
+            "SELECT *",
+            "  FROM orders",
+            "2024-05-01 12:00:01 retrying",
+        ]),
+        Instant::now(),
+    );
+    assert_eq!(
+        messages(&complete),
+        vec!["2024-05-01 12:00:00 query failed:\nSELECT *\n  FROM orders"]
+    );
+}
+
+#[test]
+fn test_entries_are_bounded_and_kept_per_instance() {
+    let mut combiner = MultilineCombiner::new(MultilineRule {
+        max_lines: 3,
+        ..preset(MultilinePreset::Node)
+    });
+    let complete = combiner.push(
+        lines(&["Error: a", "    at 1", "    at 2", "    at 3", "    at 4"]),
+        Instant::now(),
+    );
+    assert_eq!(messages(&complete), vec!["Error: a\n    at 1\n    at 2"]);
+    assert_eq!(messages(&combiner.flush()), vec!["    at 3\n    at 4"]);
+
+    let mut combiner = MultilineCombiner::new(MultilineRule {
+        max_bytes: 17,
+        ..preset(MultilinePreset::Node)
+    });
+    let complete = combiner.push(lines(&["Error: a", "    at 1", "    at 2"]), Instant::now());
+    assert_eq!(messages(&complete), vec!["Error: a\n    at 1"]);
+
+    // Replicas logging at the same time keep their own entries
+    let mut combiner = MultilineCombiner::new(preset(MultilinePreset::Node));
+    let mut input = lines(&["Error: a", "Error: b", "    at a", "    at b", "done"]);
+    for (entry, instance) in input.iter_mut().zip(["a", "b", "a", "b", "a"]) {
+        entry.source = LogSource {
+            instance: Some(instance.to_string()),
+            ..LogSource::default()
+        };
+    }
+    let complete = combiner.push(input, Instant::now());
+    assert_eq!(messages(&complete), vec!["Error: a\n    at a"]);
+    assert_eq!(
+        messages(&combiner.flush()),
+        vec!["done", "Error: b\n    at b"]
+    );
+}
+
+#[test]
+fn test_entries_wait_for_lines_of_the_next_poll_until_the_timeout() {
+    let mut combiner = MultilineCombiner::new(MultilineRule {
+        flush_timeout: Duration::from_secs(5),
+        ..preset(MultilinePreset::Java)
+    });
+    let start = Instant::now();
+
+    assert!(combiner
+        .push(lines(&["java.lang.IllegalStateException: boom"]), start)
+        .is_empty());
+    assert!(combiner
+        .push(
+            lines(&["\tat com.shop.Api.handle(Api.java:42)"]),
+            start + Duration::from_secs(2)
+        )
+        .is_empty());
+    // Nothing came in for the timeout since the last line
+    assert!(combiner
+        .push(Vec::new(), start + Duration::from_secs(6))
+        .is_empty());
+    let complete = combiner.push(Vec::new(), start + Duration::from_secs(7));
+    assert_eq!(
+        messages(&complete),
+        vec!["java.lang.IllegalStateException: boom\n\tat com.shop.Api.handle(Api.java:42)"]
+    );
+    assert!(!combiner.is_pending());
+}
+
+// Sink keeping every stored line in memory, failing on demand
+#[derive(Default)]
+struct MemorySink {
+    logs: Mutex<Vec<LogEntry>>,
+    failing: AtomicBool,
+}
+
+#[async_trait]
+impl LogSink for MemorySink {
+    async fn store_logs(&self, logs: Vec<LogEntry>) -> Result<(), Error> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(anyhow::anyhow!("sink unavailable"));
+        }
+        self.logs.lock().unwrap().extend(logs);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_sink_combines_the_same_way_when_a_batch_is_sent_again() {
+    let memory = Arc::new(MemorySink::default());
+    let sink = MultilineLogSink::new(memory.clone(), preset(MultilinePreset::Java));
+    let batch = || {
+        lines(&[
+            "starting",
+            "java.lang.IllegalStateException: boom",
+            "\tat com.shop.Api.handle(Api.java:42)",
+        ])
+    };
+
+    memory.failing.store(true, Ordering::SeqCst);
+    assert!(sink.store_logs(batch()).await.is_err());
+    memory.failing.store(false, Ordering::SeqCst);
+    sink.store_logs(batch()).await.unwrap();
+    assert_eq!(messages(&memory.logs.lock().unwrap()), vec!["starting"]);
+    // The collector keeps its checkpoint before the held back entry
+    let held_back = sink.held_back_since().unwrap();
+    assert!(held_back >= memory.logs.lock().unwrap()[0].timestamp);
+
+    // The held back entry is stored on flush
+    sink.flush().await.unwrap();
+    assert_eq!(sink.held_back_since(), None);
+    assert_eq!(
+        messages(&memory.logs.lock().unwrap()),
+        vec![
+            "starting",
+            "java.lang.IllegalStateException: boom\n\tat com.shop.Api.handle(Api.java:42)"
+        ]
+    );
+}
@@ -0,0 +1,96 @@
+use chrono::{TimeZone, Utc};
+use std::collections::BTreeMap;
+use zeabur_ops::log::log_entry::{LogEntry, LogSeverity, LogValue};
+use zeabur_ops::log::parse::{BodyParser, ParseFormat, ParseTarget};
+
+// Helper function to parse a line received at noon
+fn parsed(parser: &BodyParser, message: &str) -> LogEntry {
+    let mut entry = LogEntry::new(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap(), message);
+    parser.apply(&mut entry);
+    entry
+}
+
+#[test]
+fn test_json_fields_become_attributes_and_known_fields_are_promoted() {
+    let entry = parsed(
+        &BodyParser::default(),
+        r#"{"time":"2024-05-01T11:59:59.5Z","level":"warn","msg":"slow checkout","user":{"id":42,"plan":"pro"},"request_id":"r-1","trace_id":"4bf92f3577b34da6a3ce929d0e0e4736","span_id":"00f067aa0ba902b7","extra":null}"#,
+    );
+
+    assert_eq!(
+        entry.timestamp,
+        Utc.with_ymd_and_hms(2024, 5, 1, 11, 59, 59).unwrap() + chrono::Duration::milliseconds(500)
+    );
+    assert_eq!(entry.severity, LogSeverity::Warn);
+    assert_eq!(entry.body(), LogValue::from("slow checkout"));
+    assert_eq!(
+        entry.trace_id.as_deref(),
+        Some("4bf92f3577b34da6a3ce929d0e0e4736")
+    );
+    assert_eq!(entry.span_id.as_deref(), Some("00f067aa0ba902b7"));
+    let expected: BTreeMap<String, LogValue> = [
+        ("request_id".to_string(), LogValue::from("r-1")),
+        ("user.id".to_string(), LogValue::Int(42)),
+        ("user.plan".to_string(), LogValue::from("pro")),
+    ]
+    .into();
+    assert_eq!(entry.attributes, expected);
+    // The raw line is kept
+    assert!(entry.message.starts_with(r#"{"time""#));
+}
+
+#[test]
+fn test_map_bodies_keep_every_field() {
+    let parser = BodyParser {
+        target: ParseTarget::Body,
+        ..BodyParser::default()
+    };
+    let entry = parsed(
+        &parser,
+        r#"{"msg":"done","ts":1714564800,"user":{"id":42}}"#,
+    );
+
+    assert_eq!(entry.timestamp, Utc.timestamp_opt(1714564800, 0).unwrap());
+    assert!(entry.attributes.is_empty());
+    let LogValue::Map(body) = entry.body() else {
+        panic!("expected a map body, got {:?}", entry.body());
+    };
+    assert_eq!(body["msg"], LogValue::from("done"));
+    assert_eq!(
+        body["user"],
+        LogValue::Map([("id".to_string(), LogValue::Int(42))].into())
+    );
+}
+
+#[test]
+fn test_logfmt_messages_are_parsed() {
+    let entry = parsed(
+        &BodyParser::default(),
+        r#"level=error msg="payment \"declined\"" order_id=o-7 retried"#,
+    );
+
+    assert_eq!(entry.severity, LogSeverity::Error);
+    assert_eq!(entry.body(), LogValue::from("payment \"declined\""));
+    assert_eq!(entry.attributes["order_id"], LogValue::from("o-7"));
+    assert_eq!(entry.attributes["retried"], LogValue::Bool(true));
+}
+
+#[test]
+fn test_unparsable_messages_are_left_as_they_are() {
+    let json = BodyParser {
+        format: ParseFormat::Json,
+        ..BodyParser::default()
+    };
+    for (parser, message) in [
+        (&json, "level=info msg=not-json"),
+        (&json, r#"{"truncated":"#),
+        (&BodyParser::default(), "GET /health?full=1 200"),
+        (&BodyParser::default(), r#"msg="unterminated"#),
+        (&BodyParser::default(), "plain words only"),
+    ] {
+        let entry = parsed(parser, message);
+        assert_eq!(entry.body(), LogValue::from(message), "{:?}", message);
+        assert!(entry.attributes.is_empty());
+        assert_eq!(entry.severity, LogSeverity::Unspecified);
+    }
+}
@@ -0,0 +1,219 @@
+use anyhow::Error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use zeabur_ops::daemon::supervisor::poll_once;
+use zeabur_ops::log::log_collector::LogCollector;
+use zeabur_ops::log::log_entry::{LogEntry, LogSeverity, LogValue};
+use zeabur_ops::log::log_processor::{LogProcessor, ProcessedLogs};
+use zeabur_ops::log::log_sink::LogSink;
+use zeabur_ops::log::processor::{
+    drop_processor::DropProcessor, enrich_processor::EnrichProcessor,
+    filter_processor::FilterProcessor, log_match::LogMatch, map_processor::MapProcessor,
+    route_processor::RouteProcessor,
+};
+use zeabur_ops::log::severity::{LinePattern, SeverityClassifier};
+use zeabur_ops::log::sink::processing_log_sink::ProcessingLogSink;
+
+fn pattern(pattern: &str) -> LinePattern {
+    LinePattern::try_from(pattern.to_string()).unwrap()
+}
+
+fn entry(message: &str, severity: LogSeverity) -> LogEntry {
+    let mut entry = LogEntry::new(Utc::now(), message);
+    entry.severity = severity;
+    entry
+}
+
+fn messages(logs: &[LogEntry]) -> Vec<&str> {
+    logs.iter().map(|log| log.message.as_str()).collect()
+}
+
+// Helper function to run a processor over a few lines
+fn process(processor: &dyn LogProcessor, logs: Vec<LogEntry>) -> ProcessedLogs {
+    let mut batch = ProcessedLogs::new(logs);
+    processor.process(&mut batch);
+    batch
+}
+
+fn sample() -> Vec<LogEntry> {
+    vec![
+        entry("GET /health 200", LogSeverity::Info),
+        entry("payment declined token=abc123", LogSeverity::Warn),
+        entry("database unreachable", LogSeverity::Error),
+    ]
+}
+
+#[test]
+fn test_filter_and_drop_keep_or_remove_matching_entries() {
+    let warnings = LogMatch {
+        severity: Some(LogSeverity::Warn),
+        ..LogMatch::default()
+    };
+    let batch = process(
+        &FilterProcessor {
+            when: warnings.clone(),
+        },
+        sample(),
+    );
+    assert_eq!(
+        messages(&batch.logs),
+        vec!["payment declined token=abc123", "database unreachable"]
+    );
+
+    let health = LogMatch {
+        message: Some(pattern("^GET /health ")),
+        ..LogMatch::default()
+    };
+    let batch = process(&DropProcessor { when: health }, sample());
+    assert_eq!(batch.logs.len(), 2);
+    assert!(batch.routed.is_empty());
+}
+
+#[test]
+fn test_map_rewrites_messages_and_attributes() {
+    let redact = MapProcessor {
+        when: LogMatch::default(),
+        pattern: pattern(r"token=\w+"),
+        replacement: "token=[redacted]".to_string(),
+        attribute: None,
+    };
+    let batch = process(&redact, sample());
+    assert_eq!(batch.logs[1].message, "payment declined token=[redacted]");
+    assert_eq!(batch.logs[0].message, "GET /health 200");
+
+    let mut with_email = entry("signed up", LogSeverity::Info);
+    with_email
+        .attributes
+        .insert("email".to_string(), LogValue::from("ada@example.com"));
+    let mask = MapProcessor {
+        when: LogMatch::default(),
+        pattern: pattern(r"^[^@]+"),
+        replacement: "***".to_string(),
+        attribute: Some("email".to_string()),
+    };
+    let batch = process(&mask, vec![with_email]);
+    assert_eq!(
+        batch.logs[0].attributes["email"],
+        LogValue::from("***@example.com")
+    );
+    assert_eq!(batch.logs[0].message, "signed up");
+}
+
+#[test]
+fn test_enrich_and_route_use_matches_on_attributes() {
+    let enrich = EnrichProcessor {
+        when: LogMatch {
+            message: Some(pattern("payment")),
+            ..LogMatch::default()
+        },
+        attributes: [("team".to_string(), "billing".to_string())].into(),
+    };
+    let route = RouteProcessor {
+        when: LogMatch {
+            attributes: [("team".to_string(), pattern("^billing$"))].into(),
+            ..LogMatch::default()
+        },
+        sinks: vec!["billing".to_string()],
+        copy: false,
+    };
+    let mut batch = ProcessedLogs::new(sample());
+    enrich.process(&mut batch);
+    route.process(&mut batch);
+
+    assert_eq!(
+        messages(&batch.logs),
+        vec!["GET /health 200", "database unreachable"]
+    );
+    assert_eq!(
+        messages(&batch.routed["billing"]),
+        vec!["payment declined token=abc123"]
+    );
+    assert_eq!(
+        batch.routed["billing"][0].attributes["team"],
+        LogValue::from("billing")
+    );
+}
+
+// Collector returning the same lines on every poll
+struct MemoryCollector {
+    lines: Vec<&'static str>,
+    commits: AtomicUsize,
+}
+
+#[async_trait]
+impl LogCollector for MemoryCollector {
+    async fn collect_logs(&self) -> Result<Vec<LogEntry>, Error> {
+        Ok(self
+            .lines
+            .iter()
+            .map(|line| LogEntry::new(Utc::now(), *line))
+            .collect())
+    }
+
+    async fn commit(&self, _held_back: Option<DateTime<Utc>>) -> Result<(), Error> {
+        self.commits.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+// Sink keeping every stored line in memory
+#[derive(Default)]
+struct MemorySink {
+    logs: Mutex<Vec<LogEntry>>,
+}
+
+#[async_trait]
+impl LogSink for MemorySink {
+    async fn store_logs(&self, logs: Vec<LogEntry>) -> Result<(), Error> {
+        self.logs.lock().unwrap().extend(logs);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_chain_runs_between_the_collector_and_the_sinks() {
+    let collector = MemoryCollector {
+        lines: vec![
+            "GET /health 200",
+            "[INFO] order created",
+            "[ERROR] order failed",
+        ],
+        commits: AtomicUsize::new(0),
+    };
+    let main = Arc::new(MemorySink::default());
+    let alerts = Arc::new(MemorySink::default());
+    let processors: Vec<Arc<dyn LogProcessor>> = vec![
+        Arc::new(SeverityClassifier::default()),
+        Arc::new(DropProcessor {
+            when: LogMatch {
+                message: Some(pattern("/health")),
+                ..LogMatch::default()
+            },
+        }),
+        Arc::new(RouteProcessor {
+            when: LogMatch {
+                severity: Some(LogSeverity::Error),
+                ..LogMatch::default()
+            },
+            sinks: vec!["alerts".to_string()],
+            copy: true,
+        }),
+    ];
+    let routes: BTreeMap<String, Arc<dyn LogSink>> =
+        [("alerts".to_string(), alerts.clone() as Arc<dyn LogSink>)].into();
+    let sink = ProcessingLogSink::new(main.clone(), processors).with_routes(routes);
+
+    assert_eq!(poll_once(&collector, &sink).await.unwrap(), 3);
+    assert_eq!(collector.commits.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        messages(&main.logs.lock().unwrap()),
+        vec!["[INFO] order created", "[ERROR] order failed"]
+    );
+    assert_eq!(
+        messages(&alerts.logs.lock().unwrap()),
+        vec!["[ERROR] order failed"]
+    );
+}
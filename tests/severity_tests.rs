@@ -0,0 +1,91 @@
+use zeabur_ops::log::log_entry::LogSeverity;
+use zeabur_ops::log::severity::detect;
+
+// Helper function to check the severity detected for each line
+fn assert_detected(cases: &[(&str, Option<LogSeverity>)]) {
+    for (line, expected) in cases {
+        assert_eq!(detect(line), *expected, "{:?}", line);
+    }
+}
+
+#[test]
+fn test_structured_levels_are_detected() {
+    assert_detected(&[
+        (
+            r#"{"level":"error","msg":"payment failed"}"#,
+            Some(LogSeverity::Error),
+        ),
+        (r#"{"severity":"WARNING"}"#, Some(LogSeverity::Warn)),
+        // pino writes numeric levels
+        (r#"{"level":30,"msg":"listening"}"#, Some(LogSeverity::Info)),
+        (r#"{"level":60}"#, Some(LogSeverity::Fatal)),
+        (
+            r#"time=2024-05-01T12:00:00Z level=debug msg="cache miss""#,
+            Some(LogSeverity::Debug),
+        ),
+        (r#"lvl="warn" msg=slow"#, Some(LogSeverity::Warn)),
+        (r#"{"msg":"no level here"}"#, None),
+    ]);
+}
+
+#[test]
+fn test_level_prefixes_are_detected() {
+    assert_detected(&[
+        ("[ERROR] connection refused", Some(LogSeverity::Error)),
+        (
+            "2024-05-01 12:00:00 [warn] slow query",
+            Some(LogSeverity::Warn),
+        ),
+        ("WARN deprecated option", Some(LogSeverity::Warn)),
+        ("ERROR: relation does not exist", Some(LogSeverity::Error)),
+        (
+            "2024-05-01T12:00:00.123Z INFO server started",
+            Some(LogSeverity::Info),
+        ),
+        // Only upper case words count without brackets
+        ("Info about the request", None),
+        ("GET /health 200", None),
+    ]);
+}
+
+#[test]
+fn test_crash_reports_are_detected() {
+    assert_detected(&[
+        (
+            "thread 'main' panicked at src/main.rs:4:5:",
+            Some(LogSeverity::Fatal),
+        ),
+        (
+            "panic: runtime error: index out of range [3] with length 3",
+            Some(LogSeverity::Fatal),
+        ),
+        ("goroutine 1 [running]:", Some(LogSeverity::Fatal)),
+        ("\t/app/main.go:12 +0x1d", Some(LogSeverity::Fatal)),
+        (
+            "Traceback (most recent call last):",
+            Some(LogSeverity::Error),
+        ),
+        (
+            "  File \"/app/main.py\", line 3, in <module>",
+            Some(LogSeverity::Error),
+        ),
+        ("ValueError: invalid literal", Some(LogSeverity::Error)),
+        (
+            "Exception in thread \"main\" java.lang.IllegalStateException: boom",
+            Some(LogSeverity::Error),
+        ),
+        (
+            "java.lang.NullPointerException: name",
+            Some(LogSeverity::Error),
+        ),
+        (
+            "\tat com.shop.Api.handle(Api.java:42)",
+            Some(LogSeverity::Error),
+        ),
+        ("Caused by: java.io.IOException", Some(LogSeverity::Error)),
+        (
+            "    at handler (/app/index.js:10:15)",
+            Some(LogSeverity::Error),
+        ),
+    ]);
+}
@@ -0,0 +1,117 @@
+use serde_json::json;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use wiremock::matchers::{body_string_contains, method, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Helper function to answer the Zeabur API and the OTLP endpoint for one service
+async fn mock_backends(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(body_string_contains("GetProjects"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "projects": { "edges": [{ "node": {
+                "name": "shop",
+                "description": "",
+                "iconURL": "",
+                "_id": "shop",
+                "region": { "provider": "aws", "name": "Tokyo", "id": "aws-tokyo-1" },
+                "environments": [{ "_id": "production", "name": "production" }],
+                "owner": null,
+                "collaborators": []
+            } }] } }
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("GetServicesOfProject"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "project": { "services": [
+                { "_id": "api", "name": "api", "latestDeployment": { "status": "RUNNING" } }
+            ] } }
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("QueryServiceRuntimeLogs"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "runtimeLogs": [
+                { "timestamp": "2024-05-01T12:00:00Z", "zeaburUID": "uid", "message": "hello" }
+            ] }
+        })))
+        .mount(server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path_regex("/v1/logs$"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(server)
+        .await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_sigterm_stops_collectors_and_exits_cleanly() {
+    let server = MockServer::start().await;
+    mock_backends(&server).await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("zeabur-ops.toml");
+    std::fs::write(
+        &config,
+        format!(
+            r#"
+            checkpoint_store = "{checkpoints}"
+
+            [[accounts]]
+            api_key = "secret"
+            endpoint = "{uri}"
+
+            [polling]
+            min_interval = "100ms"
+            max_interval = "100ms"
+
+            [[sinks]]
+            name = "otlp"
+            type = "otlp_http"
+            endpoint = "{uri}/v1/logs"
+            "#,
+            checkpoints = dir.path().join("cursors.json").display(),
+            uri = server.uri(),
+        ),
+    )
+    .unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_zeabur-ops"))
+        .args(["--config", config.to_str().unwrap(), "run"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Wait until the first batch has been committed
+    let started = Instant::now();
+    while !dir.path().join("cursors.json").exists() {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "nothing was committed"
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    Command::new("kill")
+        .args(["-TERM", &child.id().to_string()])
+        .status()
+        .unwrap();
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        assert!(started.elapsed() < Duration::from_secs(10), "did not exit");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    };
+
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(status.code(), Some(0), "stdout: {}", stdout);
+    assert!(stdout.contains("Stopped 1 collectors cleanly"));
+}
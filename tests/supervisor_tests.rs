@@ -0,0 +1,205 @@
+use anyhow::Error;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use zeabur_ops::daemon::supervisor::{PollSettings, Supervisor};
+use zeabur_ops::log::log_collector::LogCollector;
+use zeabur_ops::log::log_entry::LogEntry;
+use zeabur_ops::log::log_sink::LogSink;
+use zeabur_ops::zeabur::service_key::ServiceKey;
+
+fn key(service_id: &str) -> ServiceKey {
+    ServiceKey::new(
+        "project".to_string(),
+        "environment".to_string(),
+        service_id.to_string(),
+    )
+}
+
+fn settings() -> PollSettings {
+    PollSettings {
+        min_interval: Duration::from_millis(10),
+        max_interval: Duration::from_millis(10),
+        timeout: Duration::from_millis(200),
+        restart_delay: Duration::from_millis(10),
+    }
+}
+
+// Collector returning one line per poll, panicking on the polls listed in
+// `panic_on` and sleeping for `delay` while tracking overlapping polls
+#[derive(Default)]
+struct FakeCollector {
+    polls: AtomicUsize,
+    commits: AtomicUsize,
+    panic_on: Vec<usize>,
+    delay: Duration,
+    running: Arc<AtomicUsize>,
+    max_running: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl LogCollector for FakeCollector {
+    async fn collect_logs(&self) -> Result<Vec<LogEntry>, Error> {
+        let poll = self.polls.fetch_add(1, Ordering::SeqCst);
+        if self.panic_on.contains(&poll) {
+            panic!("collector crashed on poll {}", poll);
+        }
+
+        let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_running.fetch_max(running, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        self.running.fetch_sub(1, Ordering::SeqCst);
+
+        Ok(vec![LogEntry::new(Utc::now(), format!("poll {}", poll))])
+    }
+
+    async fn commit(&self, _held_back: Option<DateTime<Utc>>) -> Result<(), Error> {
+        self.commits.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+// Sink keeping every stored line in memory
+#[derive(Default)]
+struct MemorySink {
+    logs: Mutex<Vec<LogEntry>>,
+}
+
+#[async_trait]
+impl LogSink for MemorySink {
+    async fn store_logs(&self, logs: Vec<LogEntry>) -> Result<(), Error> {
+        self.logs.lock().unwrap().extend(logs);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_panicking_poller_is_restarted() {
+    let collector = Arc::new(FakeCollector {
+        panic_on: vec![0, 2],
+        ..FakeCollector::default()
+    });
+    let sink = Arc::new(MemorySink::default());
+    let mut supervisor = Supervisor::new(4);
+
+    supervisor.start(key("api"), collector.clone(), sink.clone(), settings());
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert!(supervisor.is_running(&key("api")));
+    assert!(collector.polls.load(Ordering::SeqCst) > 3);
+    // Panicked polls never reach the sink nor commit
+    let logs = sink.logs.lock().unwrap();
+    assert_eq!(collector.commits.load(Ordering::SeqCst), logs.len());
+    assert!(logs
+        .iter()
+        .all(|log| log.message != "poll 0" && log.message != "poll 2"));
+}
+
+#[tokio::test]
+async fn test_polls_share_a_concurrency_limit() {
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let mut supervisor = Supervisor::new(2);
+
+    let collectors: Vec<Arc<FakeCollector>> = (0..5)
+        .map(|_| {
+            Arc::new(FakeCollector {
+                delay: Duration::from_millis(30),
+                running: running.clone(),
+                max_running: max_running.clone(),
+                ..FakeCollector::default()
+            })
+        })
+        .collect();
+    for (i, collector) in collectors.iter().enumerate() {
+        supervisor.start(
+            key(&i.to_string()),
+            collector.clone(),
+            Arc::new(MemorySink::default()),
+            settings(),
+        );
+    }
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert_eq!(supervisor.len(), 5);
+    assert_eq!(max_running.load(Ordering::SeqCst), 2);
+    assert!(collectors
+        .iter()
+        .all(|collector| collector.commits.load(Ordering::SeqCst) > 0));
+}
+
+#[tokio::test]
+async fn test_slow_poll_times_out_and_stopped_service_is_not_polled() {
+    let collector = Arc::new(FakeCollector {
+        delay: Duration::from_secs(10),
+        ..FakeCollector::default()
+    });
+    let mut supervisor = Supervisor::new(1);
+
+    supervisor.start(
+        key("slow"),
+        collector.clone(),
+        Arc::new(MemorySink::default()),
+        settings(),
+    );
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // Timed out polls release the shared permit and are retried
+    assert!(collector.polls.load(Ordering::SeqCst) >= 2);
+    assert_eq!(collector.commits.load(Ordering::SeqCst), 0);
+
+    assert!(supervisor.stop(&key("slow")));
+    assert!(!supervisor.stop(&key("slow")));
+    let polls = collector.polls.load(Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    assert_eq!(collector.polls.load(Ordering::SeqCst), polls);
+}
+
+#[tokio::test]
+async fn test_shutdown_waits_for_the_poll_in_flight() {
+    let collector = Arc::new(FakeCollector {
+        delay: Duration::from_millis(200),
+        ..FakeCollector::default()
+    });
+    let sink = Arc::new(MemorySink::default());
+    let mut supervisor = Supervisor::new(1);
+    supervisor.start(key("api"), collector.clone(), sink.clone(), settings());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let report = supervisor.shutdown(Duration::from_secs(5)).await;
+
+    assert!(report.is_clean());
+    assert_eq!(report.stopped, vec![key("api")]);
+    assert!(supervisor.is_empty());
+    // The batch being collected when the shutdown started was stored and committed
+    assert_eq!(collector.polls.load(Ordering::SeqCst), 1);
+    assert_eq!(collector.commits.load(Ordering::SeqCst), 1);
+    assert_eq!(sink.logs.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_shutdown_aborts_polls_past_the_deadline() {
+    let collector = Arc::new(FakeCollector {
+        delay: Duration::from_secs(10),
+        ..FakeCollector::default()
+    });
+    let mut supervisor = Supervisor::new(1);
+    let mut slow_settings = settings();
+    slow_settings.timeout = Duration::from_secs(30);
+    supervisor.start(
+        key("slow"),
+        collector.clone(),
+        Arc::new(MemorySink::default()),
+        slow_settings,
+    );
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let report = supervisor.shutdown(Duration::from_millis(100)).await;
+
+    assert!(!report.is_clean());
+    assert_eq!(report.aborted, vec![key("slow")]);
+    assert_eq!(collector.commits.load(Ordering::SeqCst), 0);
+}
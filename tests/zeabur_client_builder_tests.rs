@@ -0,0 +1,86 @@
+use serde_json::json;
+use std::time::Duration;
+use wiremock::matchers::{header, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zeabur_ops::zeabur::client::ZeaburClient;
+use zeabur_ops::zeabur::client_builder::DEFAULT_USER_AGENT;
+use zeabur_ops::zeabur::error::ZeaburError;
+use zeabur_ops::zeabur::retry::RetryPolicy;
+
+// Helper function to mount an empty projects response
+fn empty_projects() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "data": { "projects": { "edges": [] } } }))
+}
+
+#[tokio::test]
+async fn test_builder_sends_custom_headers_and_user_agent() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("User-Agent", "ops-team/1.0"))
+        .and(header("X-Request-Source", "zeabur-ops"))
+        .respond_with(empty_projects())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .user_agent("ops-team/1.0")
+        .header("X-Request-Source", "zeabur-ops")
+        .build()
+        .unwrap();
+
+    client.list_projects().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_default_client_identifies_itself() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("User-Agent", DEFAULT_USER_AGENT))
+        .respond_with(empty_projects())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .build()
+        .unwrap();
+
+    client.list_projects().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_request_timeout_surfaces_as_transport_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(empty_projects().set_delay(Duration::from_secs(2)))
+        .mount(&server)
+        .await;
+
+    let client = ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .timeout(Some(Duration::from_millis(100)))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    match client.list_projects().await {
+        Err(ZeaburError::Transport(e)) => assert!(e.is_timeout()),
+        other => panic!("Expected a timeout, got {:?}", other.map(|p| p.len())),
+    }
+}
+
+#[test]
+fn test_invalid_settings_are_rejected_at_build_time() {
+    let invalid_header = ZeaburClient::builder("test-api-key".to_string())
+        .header("Invalid Header", "value")
+        .build();
+    assert!(matches!(invalid_header, Err(ZeaburError::Config(_))));
+
+    let invalid_certificate = ZeaburClient::builder("test-api-key".to_string())
+        .add_root_certificate_pem("not a certificate")
+        .build();
+    assert!(matches!(invalid_certificate, Err(ZeaburError::Config(_))));
+}
@@ -0,0 +1,286 @@
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+use std::time::Duration;
+use wiremock::matchers::{body_json, header, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zeabur_ops::zeabur::client::ZeaburClient;
+use zeabur_ops::zeabur::error::ZeaburError;
+use zeabur_ops::zeabur::query_service_runtime_logs::QUERY_SERVICE_RUNTIME_LOGS;
+use zeabur_ops::zeabur::retry::RetryPolicy;
+
+// Helper function to create a ZeaburClient pointing at the mock server
+fn mock_client(server: &MockServer) -> ZeaburClient {
+    ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_query_service_runtime_logs_sends_variables_without_cursor() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("Authorization", "Bearer test-api-key"))
+        .and(body_json(json!({
+            "query": QUERY_SERVICE_RUNTIME_LOGS,
+            "variables": {
+                "projectID": "project-1",
+                "serviceID": "service-1",
+                "environmentID": "environment-1",
+                "timestampCursor": null
+            }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": {
+                "runtimeLogs": [
+                    {"timestamp": "2024-05-01T12:00:00.123Z", "message": "hello", "zeaburUID": "uid-1"}
+                ]
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let logs = mock_client(&server)
+        .query_service_runtime_logs("project-1", "service-1", "environment-1", None)
+        .await
+        .expect("Failed to query service runtime logs");
+
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].message, "hello");
+    assert_eq!(logs[0].zeabur_uid, "uid-1");
+}
+
+#[tokio::test]
+async fn test_query_service_runtime_logs_sends_typed_cursor() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_json(json!({
+            "query": QUERY_SERVICE_RUNTIME_LOGS,
+            "variables": {
+                "projectID": "project-1",
+                "serviceID": "service-1",
+                "environmentID": "environment-1",
+                "timestampCursor": "2024-05-01T12:00:00.123Z"
+            }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "runtimeLogs": [] }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let cursor = Utc.timestamp_millis_opt(1_714_564_800_123).unwrap();
+    let logs = mock_client(&server)
+        .query_service_runtime_logs("project-1", "service-1", "environment-1", Some(cursor))
+        .await
+        .expect("Failed to query service runtime logs with cursor");
+
+    assert!(logs.is_empty());
+}
+
+#[tokio::test]
+async fn test_query_service_runtime_logs_does_not_interpolate_ids() {
+    let server = MockServer::start().await;
+    let hostile_id = r#"x") { __typename } query { projects"#;
+    Mock::given(method("POST"))
+        .and(body_json(json!({
+            "query": QUERY_SERVICE_RUNTIME_LOGS,
+            "variables": {
+                "projectID": hostile_id,
+                "serviceID": "service-1",
+                "environmentID": "environment-1",
+                "timestampCursor": null
+            }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "runtimeLogs": [] }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    mock_client(&server)
+        .query_service_runtime_logs(hostile_id, "service-1", "environment-1", None)
+        .await
+        .expect("IDs must be sent as variables, not spliced into the query");
+}
+
+// Helper function to mount a single response for any request
+async fn mount_response(server: &MockServer, response: ResponseTemplate) {
+    Mock::given(method("POST"))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_http_401_maps_to_unauthorized() {
+    let server = MockServer::start().await;
+    mount_response(&server, ResponseTemplate::new(401)).await;
+
+    let result = mock_client(&server).list_projects().await;
+    assert!(matches!(result, Err(ZeaburError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn test_unauthenticated_graphql_error_maps_to_unauthorized() {
+    let server = MockServer::start().await;
+    mount_response(
+        &server,
+        ResponseTemplate::new(200).set_body_json(json!({
+            "data": null,
+            "errors": [{"message": "not logged in", "extensions": {"code": "UNAUTHENTICATED"}}]
+        })),
+    )
+    .await;
+
+    let result = mock_client(&server).list_projects().await;
+    assert!(matches!(result, Err(ZeaburError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn test_http_429_maps_to_rate_limited_with_retry_after() {
+    let server = MockServer::start().await;
+    mount_response(
+        &server,
+        ResponseTemplate::new(429).insert_header("Retry-After", "7"),
+    )
+    .await;
+
+    let result = mock_client(&server).list_projects().await;
+    match result {
+        Err(ZeaburError::RateLimited { retry_after }) => {
+            assert_eq!(retry_after, Some(Duration::from_secs(7)))
+        }
+        other => panic!("Expected RateLimited, got {:?}", other.map(|p| p.len())),
+    }
+}
+
+#[tokio::test]
+async fn test_graphql_errors_map_to_graphql_error() {
+    let server = MockServer::start().await;
+    mount_response(
+        &server,
+        ResponseTemplate::new(200).set_body_json(json!({
+            "data": null,
+            "errors": [{"message": "project not found", "path": ["project", "services", 0]}]
+        })),
+    )
+    .await;
+
+    let result = mock_client(&server)
+        .get_services_of_project("project-1", "environment-1")
+        .await;
+    match result {
+        Err(ZeaburError::GraphQL { messages, path }) => {
+            assert_eq!(messages, vec!["project not found"]);
+            assert_eq!(path, vec!["project", "services", "0"]);
+        }
+        other => panic!("Expected GraphQL error, got {:?}", other.map(|s| s.len())),
+    }
+}
+
+#[tokio::test]
+async fn test_server_error_maps_to_http_error() {
+    let server = MockServer::start().await;
+    mount_response(
+        &server,
+        ResponseTemplate::new(502).set_body_string("bad gateway"),
+    )
+    .await;
+
+    let result = mock_client(&server)
+        .get_environments_of_project("project-1")
+        .await;
+    match result {
+        Err(ZeaburError::Http { status, body }) => {
+            assert_eq!(status.as_u16(), 502);
+            assert_eq!(body, "bad gateway");
+        }
+        other => panic!("Expected Http error, got {:?}", other.map(|p| p.id)),
+    }
+}
+
+#[tokio::test]
+async fn test_non_json_body_maps_to_decode_error() {
+    let server = MockServer::start().await;
+    mount_response(
+        &server,
+        ResponseTemplate::new(200).set_body_string("<html>"),
+    )
+    .await;
+
+    let result = mock_client(&server)
+        .query_service_runtime_logs("project-1", "service-1", "environment-1", None)
+        .await;
+    assert!(matches!(result, Err(ZeaburError::Decode(_))));
+}
+
+#[tokio::test]
+async fn test_unreachable_endpoint_maps_to_transport_error() {
+    let client = ZeaburClient::builder("test-api-key".to_string())
+        .endpoint("http://127.0.0.1:9")
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+
+    let result = client.list_projects().await;
+    assert!(matches!(result, Err(ZeaburError::Transport(_))));
+}
+
+#[tokio::test]
+async fn test_query_service_runtime_logs_between_walks_back_pages() {
+    let server = MockServer::start().await;
+    let page = |cursor: &str, logs: serde_json::Value| {
+        Mock::given(method("POST"))
+            .and(wiremock::matchers::body_partial_json(
+                json!({ "variables": { "timestampCursor": cursor } }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "data": { "runtimeLogs": logs } })),
+            )
+    };
+    page(
+        "2024-05-01T11:45:00Z",
+        json!([
+            { "timestamp": "2024-05-01T11:45:00Z", "zeaburUID": "uid", "message": "too late" },
+            { "timestamp": "2024-05-01T11:30:00Z", "zeaburUID": "uid", "message": "third" },
+            { "timestamp": "2024-05-01T11:20:00Z", "zeaburUID": "uid", "message": "second" },
+        ]),
+    )
+    .mount(&server)
+    .await;
+    // Overlaps the first page on the line at the cursor
+    page(
+        "2024-05-01T11:20:00Z",
+        json!([
+            { "timestamp": "2024-05-01T11:20:00Z", "zeaburUID": "uid", "message": "second" },
+            { "timestamp": "2024-05-01T11:10:00Z", "zeaburUID": "uid", "message": "first" },
+            { "timestamp": "2024-05-01T10:50:00Z", "zeaburUID": "uid", "message": "too early" },
+        ]),
+    )
+    .mount(&server)
+    .await;
+
+    let client = mock_client(&server);
+    let key = zeabur_ops::zeabur::service_key::ServiceKey::new(
+        "project".to_string(),
+        "environment".to_string(),
+        "service".to_string(),
+    );
+    let logs = client
+        .query_service_runtime_logs_between(
+            &key,
+            Utc.with_ymd_and_hms(2024, 5, 1, 11, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 5, 1, 11, 45, 0).unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let messages: Vec<&str> = logs.iter().map(|log| log.log.message.as_str()).collect();
+    assert_eq!(messages, vec!["first", "second", "third"]);
+}
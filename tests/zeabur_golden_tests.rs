@@ -0,0 +1,136 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zeabur_ops::zeabur::client::ZeaburClient;
+use zeabur_ops::zeabur::error::ZeaburError;
+use zeabur_ops::zeabur::retry::RetryPolicy;
+
+// Helper function to load a fixture from tests/fixtures/zeabur
+fn fixture(name: &str) -> Value {
+    let path = format!(
+        "{}/tests/fixtures/zeabur/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    );
+    let content = std::fs::read_to_string(&path).unwrap_or_else(|_| panic!("{} is missing", path));
+    serde_json::from_str(&content).unwrap_or_else(|e| panic!("{} is not JSON: {}", path, e))
+}
+
+// Helper function to create a client which gets the given response body
+async fn client_responding_with(server: &MockServer, body: Value) -> ZeaburClient {
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(server)
+        .await;
+    ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap()
+}
+
+// Compare the decoded value with the golden file of the operation
+fn assert_golden(operation: &str, decoded: impl Serialize) {
+    let expected = fixture(&format!("{}.golden.json", operation));
+    assert_eq!(serde_json::to_value(decoded).unwrap(), expected);
+}
+
+#[tokio::test]
+async fn test_list_projects_golden() {
+    let server = MockServer::start().await;
+    let client = client_responding_with(&server, fixture("list_projects.response.json")).await;
+
+    assert_golden("list_projects", client.list_projects().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_get_environments_of_project_golden() {
+    let server = MockServer::start().await;
+    let client = client_responding_with(
+        &server,
+        fixture("get_environments_of_project.response.json"),
+    )
+    .await;
+
+    let project = client
+        .get_environments_of_project("65f0c0ffee0000000000a001")
+        .await
+        .unwrap();
+    assert_golden("get_environments_of_project", project);
+}
+
+#[tokio::test]
+async fn test_get_services_of_project_golden() {
+    let server = MockServer::start().await;
+    let client =
+        client_responding_with(&server, fixture("get_services_of_project.response.json")).await;
+
+    let services = client
+        .get_services_of_project("65f0c0ffee0000000000a001", "65f0c0ffee0000000000e001")
+        .await
+        .unwrap();
+    assert_golden("get_services_of_project", services);
+}
+
+#[tokio::test]
+async fn test_query_service_runtime_logs_golden() {
+    let server = MockServer::start().await;
+    let client =
+        client_responding_with(&server, fixture("query_service_runtime_logs.response.json")).await;
+
+    let logs = client
+        .query_service_runtime_logs("project", "service", "environment", None)
+        .await
+        .unwrap();
+    assert_golden("query_service_runtime_logs", logs);
+}
+
+#[tokio::test]
+async fn test_missing_required_field_is_a_decode_error() {
+    let server = MockServer::start().await;
+    let mut response = fixture("get_services_of_project.response.json");
+    response["data"]["project"]["services"][0]
+        .as_object_mut()
+        .unwrap()
+        .remove("name");
+    let client = client_responding_with(&server, response).await;
+
+    match client
+        .get_services_of_project("project", "environment")
+        .await
+    {
+        Err(ZeaburError::Decode(message)) => assert!(message.contains("name"), "{}", message),
+        other => panic!("Expected a decode error, got {:?}", other.map(|s| s.len())),
+    }
+}
+
+#[tokio::test]
+async fn test_renamed_field_is_a_decode_error() {
+    let server = MockServer::start().await;
+    let client = client_responding_with(
+        &server,
+        json!({
+            "data": {
+                "runtimeLogs": [
+                    {"timestamp": "2024-05-01T12:00:00Z", "msg": "renamed", "zeaburUID": "uid"}
+                ]
+            }
+        }),
+    )
+    .await;
+
+    let result = client
+        .query_service_runtime_logs("project", "service", "environment", None)
+        .await;
+    assert!(matches!(result, Err(ZeaburError::Decode(_))));
+}
+
+#[tokio::test]
+async fn test_missing_project_is_a_decode_error() {
+    let server = MockServer::start().await;
+    let client = client_responding_with(&server, json!({ "data": { "project": null } })).await;
+
+    let result = client.get_environments_of_project("unknown").await;
+    assert!(matches!(result, Err(ZeaburError::Decode(_))));
+}
@@ -52,7 +52,7 @@ async fn test_query_service_runtime_logs_with_timestamp() {
     let (client, project_id, service_id, environment_id) = setup_client();
 
     // Get the current timestamp
-    let current_timestamp = Utc::now().to_rfc3339();
+    let current_timestamp = Utc::now();
 
     // Query service runtime logs with current timestamp
     let logs_with_timestamp = client
@@ -60,7 +60,7 @@ async fn test_query_service_runtime_logs_with_timestamp() {
             &project_id,
             &service_id,
             &environment_id,
-            Some(&current_timestamp),
+            Some(current_timestamp),
         )
         .await
         .expect("Failed to query service runtime logs with timestamp");
@@ -0,0 +1,439 @@
+use serde_json::{json, Value};
+use std::sync::Arc;
+use wiremock::matchers::{body_partial_json, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zeabur_ops::log::checkpoint::file_checkpoint_store::FileCheckpointStore;
+use zeabur_ops::log::checkpoint_store::{Checkpoint, CheckpointStore};
+use zeabur_ops::log::log_collector::{LogCollector, PollLoad};
+use zeabur_ops::log::log_entry::{LogSeverity, LogSource, LogValue};
+use zeabur_ops::log::zeabur_log_collector::{CollectorMode, ZeaburServiceLogCollector};
+use zeabur_ops::zeabur::client::ZeaburClient;
+
+// Helper function to build a runtimeLogs response
+fn runtime_logs(lines: &[(&str, &str, &str)]) -> ResponseTemplate {
+    let logs: Vec<Value> = lines
+        .iter()
+        .map(|(timestamp, uid, message)| {
+            json!({"timestamp": timestamp, "zeaburUID": uid, "message": message})
+        })
+        .collect();
+    ResponseTemplate::new(200).set_body_json(json!({ "data": { "runtimeLogs": logs } }))
+}
+
+// Helper function to mount a response for a given timestampCursor variable
+async fn mount_page(server: &MockServer, cursor: Value, response: ResponseTemplate) {
+    Mock::given(method("POST"))
+        .and(body_partial_json(
+            json!({ "variables": { "timestampCursor": cursor } }),
+        ))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+// Helper function to create a collector pointing at the mock server
+fn collector(server: &MockServer) -> ZeaburServiceLogCollector {
+    let client = ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .build()
+        .unwrap();
+    ZeaburServiceLogCollector::new(
+        "project".to_string(),
+        "environment".to_string(),
+        "service".to_string(),
+        client,
+    )
+}
+
+fn messages(logs: &[zeabur_ops::log::log_entry::LogEntry]) -> Vec<&str> {
+    logs.iter().map(|log| log.message.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_collector_keeps_lines_sharing_the_last_timestamp() {
+    let server = MockServer::start().await;
+    let collector = collector(&server);
+
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[
+            ("2024-05-01T12:00:00.001Z", "uid", "first"),
+            ("2024-05-01T12:00:00.002Z", "uid", "second"),
+        ]),
+    )
+    .await;
+    let logs = collector.collect_logs().await.unwrap();
+    assert_eq!(messages(&logs), vec!["first", "second"]);
+    collector.commit(None).await.unwrap();
+
+    // A new line arrives in the same millisecond as the last shipped one
+    server.reset().await;
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[
+            ("2024-05-01T12:00:00.001Z", "uid", "first"),
+            ("2024-05-01T12:00:00.002Z", "uid", "second"),
+            (
+                "2024-05-01T12:00:00.002Z",
+                "uid",
+                "second, but another line",
+            ),
+        ]),
+    )
+    .await;
+    let logs = collector.collect_logs().await.unwrap();
+    assert_eq!(messages(&logs), vec!["second, but another line"]);
+}
+
+#[tokio::test]
+async fn test_collector_reships_batch_that_was_not_committed() {
+    let server = MockServer::start().await;
+    let collector = collector(&server);
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[("2024-05-01T12:00:00Z", "uid", "only")]),
+    )
+    .await;
+
+    assert_eq!(collector.collect_logs().await.unwrap().len(), 1);
+    // The sink failed, so commit is never called
+    assert_eq!(collector.collect_logs().await.unwrap().len(), 1);
+    collector.commit(None).await.unwrap();
+    assert!(collector.collect_logs().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_checkpoint_stays_before_lines_the_sink_holds_back() {
+    let server = MockServer::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(
+        FileCheckpointStore::open(dir.path().join("checkpoints.json"))
+            .await
+            .unwrap(),
+    );
+    let collector = collector(&server).with_checkpoint_store(store.clone());
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[
+            ("2024-05-01T12:00:00Z", "uid", "Error: boom"),
+            ("2024-05-01T12:00:01Z", "uid", "    at handle (api.js:1:1)"),
+        ]),
+    )
+    .await;
+    let checkpoint = |timestamp: &str| {
+        Some(Checkpoint {
+            timestamp: timestamp.parse().unwrap(),
+        })
+    };
+
+    // The sink holds back the entry begun by the first line
+    collector.collect_logs().await.unwrap();
+    collector
+        .commit(Some("2024-05-01T12:00:00Z".parse().unwrap()))
+        .await
+        .unwrap();
+    assert_eq!(
+        store.load(collector.key()).await.unwrap(),
+        checkpoint("2024-05-01T12:00:00Z")
+    );
+    // The cursor itself moved on, the lines are not shipped twice
+    assert!(collector.collect_logs().await.unwrap().is_empty());
+
+    // Once the entry is stored the checkpoint catches up
+    collector.commit(None).await.unwrap();
+    assert_eq!(
+        store.load(collector.key()).await.unwrap(),
+        checkpoint("2024-05-01T12:00:01Z")
+    );
+}
+
+#[tokio::test]
+async fn test_collector_backfills_down_to_the_checkpoint() {
+    let server = MockServer::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(
+        FileCheckpointStore::open(dir.path().join("checkpoints.json"))
+            .await
+            .unwrap(),
+    );
+    let checkpoint = Checkpoint {
+        timestamp: "2024-05-01T12:00:01Z".parse().unwrap(),
+    };
+    let collector = collector(&server)
+        .with_checkpoint_store(store.clone())
+        .with_mode(CollectorMode::Backfill {
+            since: None,
+            page_budget: 2,
+        });
+    store.commit(collector.key(), &checkpoint).await.unwrap();
+
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[
+            ("2024-05-01T12:00:05Z", "uid", "5"),
+            ("2024-05-01T12:00:06Z", "uid", "6"),
+        ]),
+    )
+    .await;
+    mount_page(
+        &server,
+        json!("2024-05-01T12:00:05Z"),
+        runtime_logs(&[
+            ("2024-05-01T12:00:03Z", "uid", "3"),
+            ("2024-05-01T12:00:04Z", "uid", "4"),
+        ]),
+    )
+    .await;
+    mount_page(
+        &server,
+        json!("2024-05-01T12:00:03Z"),
+        runtime_logs(&[
+            ("2024-05-01T12:00:00Z", "uid", "0"),
+            ("2024-05-01T12:00:02Z", "uid", "2"),
+        ]),
+    )
+    .await;
+
+    // The page budget stops the walk after one page behind the head
+    let logs = collector.collect_logs().await.unwrap();
+    assert_eq!(messages(&logs), vec!["3", "4", "5", "6"]);
+    collector.commit(None).await.unwrap();
+    assert!(collector.is_backfilling().await);
+    assert_eq!(store.load(collector.key()).await.unwrap(), Some(checkpoint));
+
+    // The next poll finishes the walk and stops at the checkpoint
+    let logs = collector.collect_logs().await.unwrap();
+    assert_eq!(messages(&logs), vec!["2"]);
+    collector.commit(None).await.unwrap();
+    assert!(!collector.is_backfilling().await);
+    assert_eq!(
+        store.load(collector.key()).await.unwrap(),
+        Some(Checkpoint {
+            timestamp: "2024-05-01T12:00:06Z".parse().unwrap(),
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_collector_queues_gaps_opening_during_a_backfill() {
+    let server = MockServer::start().await;
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(
+        FileCheckpointStore::open(dir.path().join("checkpoints.json"))
+            .await
+            .unwrap(),
+    );
+    let collector = collector(&server)
+        .with_checkpoint_store(store.clone())
+        .with_mode(CollectorMode::Backfill {
+            since: None,
+            page_budget: 2,
+        });
+    store
+        .commit(
+            collector.key(),
+            &Checkpoint {
+                timestamp: "2024-05-01T12:00:01Z".parse().unwrap(),
+            },
+        )
+        .await
+        .unwrap();
+    // The head changes between polls, the pages behind it do not
+    let mount = |head: Vec<(&'static str, &'static str, &'static str)>| {
+        let server = &server;
+        async move {
+            server.reset().await;
+            mount_page(server, Value::Null, runtime_logs(&head)).await;
+            for (cursor, page) in [
+                (
+                    "2024-05-01T12:00:05Z",
+                    [
+                        ("2024-05-01T12:00:03Z", "uid", "3"),
+                        ("2024-05-01T12:00:04Z", "uid", "4"),
+                    ],
+                ),
+                (
+                    "2024-05-01T12:00:03Z",
+                    [
+                        ("2024-05-01T12:00:00Z", "uid", "0"),
+                        ("2024-05-01T12:00:02Z", "uid", "2"),
+                    ],
+                ),
+                (
+                    "2024-05-01T12:00:09Z",
+                    [
+                        ("2024-05-01T12:00:07Z", "uid", "7"),
+                        ("2024-05-01T12:00:08Z", "uid", "8"),
+                    ],
+                ),
+                (
+                    "2024-05-01T12:00:07Z",
+                    [
+                        ("2024-05-01T12:00:05Z", "uid", "5"),
+                        ("2024-05-01T12:00:06Z", "uid", "6"),
+                    ],
+                ),
+            ] {
+                mount_page(server, json!(cursor), runtime_logs(&page)).await;
+            }
+        }
+    };
+
+    mount(vec![
+        ("2024-05-01T12:00:05Z", "uid", "5"),
+        ("2024-05-01T12:00:06Z", "uid", "6"),
+    ])
+    .await;
+    let logs = collector.collect_logs().await.unwrap();
+    assert_eq!(messages(&logs), vec!["3", "4", "5", "6"]);
+    collector.commit(None).await.unwrap();
+
+    // A second gap opens before the first one is walked, it waits its turn
+    mount(vec![
+        ("2024-05-01T12:00:09Z", "uid", "9"),
+        ("2024-05-01T12:00:10Z", "uid", "10"),
+    ])
+    .await;
+    let logs = collector.collect_logs().await.unwrap();
+    assert_eq!(messages(&logs), vec!["2", "9", "10"]);
+    collector.commit(None).await.unwrap();
+    assert!(collector.is_backfilling().await);
+    assert_eq!(
+        store.load(collector.key()).await.unwrap(),
+        Some(Checkpoint {
+            timestamp: "2024-05-01T12:00:06Z".parse().unwrap(),
+        })
+    );
+
+    let logs = collector.collect_logs().await.unwrap();
+    assert_eq!(messages(&logs), vec!["7", "8"]);
+    collector.commit(None).await.unwrap();
+    assert!(collector.collect_logs().await.unwrap().is_empty());
+    collector.commit(None).await.unwrap();
+    assert!(!collector.is_backfilling().await);
+    assert_eq!(
+        store.load(collector.key()).await.unwrap(),
+        Some(Checkpoint {
+            timestamp: "2024-05-01T12:00:10Z".parse().unwrap(),
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_collector_reports_poll_load() {
+    let server = MockServer::start().await;
+    let collector = collector(&server).with_page_size(3);
+
+    // The head page is full
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[
+            ("2024-05-01T12:00:01Z", "uid", "1"),
+            ("2024-05-01T12:00:02Z", "uid", "2"),
+            ("2024-05-01T12:00:03Z", "uid", "3"),
+        ]),
+    )
+    .await;
+    collector.collect_logs().await.unwrap();
+    collector.commit(None).await.unwrap();
+    assert_eq!(collector.poll_load().await, PollLoad::Saturated);
+
+    // Nothing new since the previous poll
+    server.reset().await;
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[
+            ("2024-05-01T12:00:02Z", "uid", "2"),
+            ("2024-05-01T12:00:03Z", "uid", "3"),
+        ]),
+    )
+    .await;
+    assert!(collector.collect_logs().await.unwrap().is_empty());
+    collector.commit(None).await.unwrap();
+    assert_eq!(collector.poll_load().await, PollLoad::Idle);
+
+    // New lines overlapping with the previous poll
+    server.reset().await;
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[
+            ("2024-05-01T12:00:03Z", "uid", "3"),
+            ("2024-05-01T12:00:04Z", "uid", "4"),
+        ]),
+    )
+    .await;
+    assert_eq!(collector.collect_logs().await.unwrap().len(), 1);
+    collector.commit(None).await.unwrap();
+    assert_eq!(collector.poll_load().await, PollLoad::Steady);
+
+    // The head page does not reach back to the previous poll
+    server.reset().await;
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[("2024-05-01T12:00:09Z", "uid", "9")]),
+    )
+    .await;
+    collector.collect_logs().await.unwrap();
+    assert_eq!(collector.poll_load().await, PollLoad::Saturated);
+}
+
+#[tokio::test]
+async fn test_collector_stamps_lines_with_their_source() {
+    let server = MockServer::start().await;
+    let collector = collector(&server).with_source(LogSource {
+        account: "team".to_string(),
+        project_id: "project".to_string(),
+        service_name: "api".to_string(),
+        deployment_id: Some("deployment-1".to_string()),
+        ..LogSource::default()
+    });
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[
+            ("2024-05-01T12:00:00Z", "uid-a", "from a"),
+            ("2024-05-01T12:00:01Z", "uid-b", "from b"),
+        ]),
+    )
+    .await;
+
+    let logs = collector.collect_logs().await.unwrap();
+    let instances: Vec<_> = logs
+        .iter()
+        .map(|log| log.source.instance.as_deref())
+        .collect();
+    assert_eq!(instances, vec![Some("uid-a"), Some("uid-b")]);
+    assert!(logs.iter().all(|log| log.source.account == "team"
+        && log.source.deployment_id.as_deref() == Some("deployment-1")
+        && log.severity == LogSeverity::Unspecified
+        && log.body() == LogValue::from(log.message.as_str())
+        && log.observed_timestamp >= log.timestamp));
+
+    // A new deployment is picked up by the next poll
+    collector.commit(None).await.unwrap();
+    collector.set_source(LogSource {
+        deployment_id: Some("deployment-2".to_string()),
+        ..collector.source()
+    });
+    server.reset().await;
+    mount_page(
+        &server,
+        Value::Null,
+        runtime_logs(&[("2024-05-01T12:00:02Z", "uid-a", "after")]),
+    )
+    .await;
+    let logs = collector.collect_logs().await.unwrap();
+    assert_eq!(
+        logs[0].source.deployment_id.as_deref(),
+        Some("deployment-2")
+    );
+}
@@ -0,0 +1,134 @@
+use serde_json::json;
+use std::time::{Duration, Instant};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zeabur_ops::zeabur::client::ZeaburClient;
+use zeabur_ops::zeabur::rate_limit::{OperationLimit, RateLimits};
+
+// Helper function to mount an empty runtimeLogs response with a fixed latency
+async fn mount_runtime_logs(server: &MockServer, latency: Duration) {
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "data": { "runtimeLogs": [] } }))
+                .set_delay(latency),
+        )
+        .mount(server)
+        .await;
+}
+
+// Helper function to create a client with the given log budget
+fn limited_client(server: &MockServer, logs: OperationLimit) -> ZeaburClient {
+    ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .rate_limits(RateLimits {
+            logs,
+            ..RateLimits::unlimited()
+        })
+        .build()
+        .unwrap()
+}
+
+async fn query_logs(client: ZeaburClient) {
+    client
+        .query_service_runtime_logs("project", "service", "environment", None)
+        .await
+        .expect("Failed to query service runtime logs");
+}
+
+#[tokio::test]
+async fn test_token_bucket_throttles_bursts_across_clones() {
+    let server = MockServer::start().await;
+    mount_runtime_logs(&server, Duration::ZERO).await;
+    let client = limited_client(
+        &server,
+        OperationLimit {
+            requests_per_second: 10.0,
+            burst: 1,
+            max_concurrency: 16,
+        },
+    );
+
+    // One token is available immediately, the next four refill at 10 per second
+    let started = Instant::now();
+    let tasks: Vec<_> = (0..5)
+        .map(|_| tokio::spawn(query_logs(client.clone())))
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    assert!(started.elapsed() >= Duration::from_millis(380));
+}
+
+#[tokio::test]
+async fn test_concurrency_cap_serializes_requests() {
+    let server = MockServer::start().await;
+    mount_runtime_logs(&server, Duration::from_millis(150)).await;
+    let client = limited_client(
+        &server,
+        OperationLimit {
+            max_concurrency: 1,
+            ..OperationLimit::unlimited()
+        },
+    );
+
+    let started = Instant::now();
+    let tasks: Vec<_> = (0..3)
+        .map(|_| tokio::spawn(query_logs(client.clone())))
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    assert!(started.elapsed() >= Duration::from_millis(450));
+}
+
+#[tokio::test]
+async fn test_discovery_budget_is_separate_from_logs() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "data": { "projects": { "edges": [] } } })),
+        )
+        .mount(&server)
+        .await;
+    // An exhausted log budget must not hold back discovery calls
+    let client = limited_client(
+        &server,
+        OperationLimit {
+            requests_per_second: 0.1,
+            burst: 1,
+            max_concurrency: 1,
+        },
+    );
+
+    let started = Instant::now();
+    for _ in 0..3 {
+        client.list_projects().await.unwrap();
+    }
+
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
+
+#[tokio::test]
+async fn test_rates_which_barely_refill_wait_instead_of_panicking() {
+    let server = MockServer::start().await;
+    mount_runtime_logs(&server, Duration::ZERO).await;
+
+    for rate in [0.0, -1.0, f64::NAN, 1e-300] {
+        let client = limited_client(
+            &server,
+            OperationLimit {
+                requests_per_second: rate,
+                burst: 1,
+                max_concurrency: 16,
+            },
+        );
+        query_logs(client.clone()).await;
+        // The next token is far away
+        let next = tokio::time::timeout(Duration::from_millis(50), query_logs(client));
+        assert!(next.await.is_err(), "rate {}", rate);
+    }
+}
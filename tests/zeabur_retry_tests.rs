@@ -0,0 +1,161 @@
+use serde_json::json;
+use std::time::{Duration, Instant};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zeabur_ops::zeabur::client::ZeaburClient;
+use zeabur_ops::zeabur::error::ZeaburError;
+use zeabur_ops::zeabur::retry::RetryPolicy;
+
+// Helper function to create a client with fast retries against the mock server
+fn retrying_client(server: &MockServer, max_attempts: u32) -> ZeaburClient {
+    ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .retry_policy(RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+            jitter: 0.5,
+            ..RetryPolicy::default()
+        })
+        .build()
+        .unwrap()
+}
+
+// Helper function to mount a response for the first `times` requests only
+async fn mount_failures(server: &MockServer, response: ResponseTemplate, times: u64) {
+    Mock::given(method("POST"))
+        .respond_with(response)
+        .up_to_n_times(times)
+        .with_priority(1)
+        .mount(server)
+        .await;
+}
+
+// Helper function to mount a successful, empty projects response
+async fn mount_success(server: &MockServer, expected_calls: u64) {
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "projects": { "edges": [] } }
+        })))
+        .expect(expected_calls)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_transient_bad_gateway_is_retried() {
+    let server = MockServer::start().await;
+    mount_failures(&server, ResponseTemplate::new(502), 2).await;
+    mount_success(&server, 1).await;
+
+    let projects = retrying_client(&server, 3)
+        .list_projects()
+        .await
+        .expect("Request should succeed after retries");
+    assert!(projects.is_empty());
+}
+
+#[tokio::test]
+async fn test_retries_give_up_after_max_attempts() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let result = retrying_client(&server, 3).list_projects().await;
+    assert!(matches!(result, Err(ZeaburError::Http { .. })));
+}
+
+#[tokio::test]
+async fn test_unauthorized_is_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(401))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let result = retrying_client(&server, 5).list_projects().await;
+    assert!(matches!(result, Err(ZeaburError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn test_retry_after_is_honoured() {
+    let server = MockServer::start().await;
+    mount_failures(
+        &server,
+        ResponseTemplate::new(429).insert_header("Retry-After", "1"),
+        1,
+    )
+    .await;
+    mount_success(&server, 1).await;
+
+    // Retry-After is honoured up to the maximum delay
+    let client = ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .retry_policy(RetryPolicy {
+            max_attempts: 2,
+            max_delay: Duration::from_secs(5),
+            ..RetryPolicy::default()
+        })
+        .build()
+        .unwrap();
+    let started = Instant::now();
+    client
+        .list_projects()
+        .await
+        .expect("Request should succeed after waiting");
+    assert!(started.elapsed() >= Duration::from_secs(1));
+}
+
+#[test]
+fn test_backoff_grows_exponentially_and_is_capped() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(350),
+        jitter: 0.0,
+        ..RetryPolicy::default()
+    };
+    let error = ZeaburError::Decode("irrelevant".to_string());
+
+    assert_eq!(policy.delay_for(1, &error), Duration::from_millis(100));
+    assert_eq!(policy.delay_for(2, &error), Duration::from_millis(200));
+    assert_eq!(policy.delay_for(3, &error), Duration::from_millis(350));
+}
+
+#[test]
+fn test_jitter_only_shortens_the_delay() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        jitter: 0.5,
+        ..RetryPolicy::default()
+    };
+    let error = ZeaburError::Decode("irrelevant".to_string());
+
+    for _ in 0..100 {
+        let delay = policy.delay_for(1, &error);
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+    }
+}
+
+#[test]
+fn test_retry_after_is_capped_by_the_maximum_delay() {
+    let policy = RetryPolicy {
+        max_delay: Duration::from_secs(30),
+        ..RetryPolicy::default()
+    };
+    let rate_limited = |seconds| ZeaburError::RateLimited {
+        retry_after: Some(Duration::from_secs(seconds)),
+    };
+
+    assert_eq!(
+        policy.delay_for(1, &rate_limited(5)),
+        Duration::from_secs(5)
+    );
+    assert_eq!(
+        policy.delay_for(1, &rate_limited(86400)),
+        Duration::from_secs(30)
+    );
+}
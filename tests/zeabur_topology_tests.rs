@@ -0,0 +1,184 @@
+use serde_json::{json, Value};
+use wiremock::matchers::{body_partial_json, body_string_contains, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zeabur_ops::zeabur::client::ZeaburClient;
+use zeabur_ops::zeabur::retry::RetryPolicy;
+use zeabur_ops::zeabur::service_key::ServiceKey;
+
+// Helper function to build a project node of the GetProjects response
+fn project(id: &str, environments: &[(&str, &str)]) -> Value {
+    json!({
+        "node": {
+            "name": format!("{}-name", id),
+            "description": "",
+            "iconURL": "",
+            "_id": id,
+            "region": { "provider": "aws", "name": "Tokyo", "id": "aws-tokyo-1" },
+            "environments": environments
+                .iter()
+                .map(|(id, name)| json!({ "_id": id, "name": name }))
+                .collect::<Vec<_>>(),
+            "owner": null,
+            "collaborators": []
+        }
+    })
+}
+
+// Helper function to build a service, deployed when a status is given
+fn service(id: &str, status: Option<&str>) -> Value {
+    json!({
+        "_id": id,
+        "name": format!("{}-name", id),
+        "latestDeployment": status.map(|status| json!({ "status": status })),
+    })
+}
+
+// Helper function to mount the services of a project in one environment
+async fn mount_services(server: &MockServer, environment_id: &str, response: ResponseTemplate) {
+    Mock::given(method("POST"))
+        .and(body_string_contains("GetServicesOfProject"))
+        .and(body_partial_json(
+            json!({ "variables": { "environmentID": environment_id } }),
+        ))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+async fn mock_zeabur(server: &MockServer) -> ZeaburClient {
+    Mock::given(method("POST"))
+        .and(body_string_contains("GetProjects"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": {
+                "projects": {
+                    "edges": [
+                        project("shop", &[("production", "Production"), ("staging", "Staging")]),
+                        project("empty", &[]),
+                    ]
+                }
+            }
+        })))
+        .mount(server)
+        .await;
+
+    ZeaburClient::builder("test-api-key".to_string())
+        .endpoint(server.uri())
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap()
+}
+
+fn key(environment_id: &str, service_id: &str) -> ServiceKey {
+    ServiceKey::new(
+        "shop".to_string(),
+        environment_id.to_string(),
+        service_id.to_string(),
+    )
+}
+
+#[tokio::test]
+async fn test_topology_is_discovered_per_environment() {
+    let server = MockServer::start().await;
+    let client = mock_zeabur(&server).await;
+    mount_services(
+        &server,
+        "production",
+        ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "project": { "services": [
+                service("api", Some("RUNNING")),
+                service("worker", Some("RUNNING")),
+            ] } }
+        })),
+    )
+    .await;
+    mount_services(
+        &server,
+        "staging",
+        ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "project": { "services": [
+                service("api", Some("BUILDING")),
+                service("worker", None),
+            ] } }
+        })),
+    )
+    .await;
+
+    let topology = client.discover_topology().await.unwrap();
+
+    assert!(topology.is_complete());
+    let keys: Vec<&ServiceKey> = topology.services.keys().collect();
+    assert_eq!(
+        keys,
+        vec![
+            &key("production", "api"),
+            &key("production", "worker"),
+            &key("staging", "api"),
+        ]
+    );
+    // The worker is only deployed in production, so it is not part of staging
+    let worker_environments: Vec<&str> = topology
+        .select(None, None, "worker")
+        .iter()
+        .map(|s| s.key.environment_id.as_str())
+        .collect();
+    assert_eq!(worker_environments, vec!["production"]);
+
+    // Each pair keeps the deployment of its own environment
+    let staging_api = &topology.services[&key("staging", "api")];
+    assert_eq!(staging_api.environment_name, "Staging");
+    assert_eq!(
+        staging_api.latest_deployment.status.as_deref(),
+        Some("BUILDING")
+    );
+    let production_api = &topology.services[&key("production", "api")];
+    assert_eq!(
+        production_api.latest_deployment.status.as_deref(),
+        Some("RUNNING")
+    );
+}
+
+#[tokio::test]
+async fn test_failed_environment_marks_project_as_failed() {
+    let server = MockServer::start().await;
+    let client = mock_zeabur(&server).await;
+    mount_services(
+        &server,
+        "production",
+        ResponseTemplate::new(200).set_body_json(json!({
+            "data": { "project": { "services": [service("api", Some("RUNNING"))] } }
+        })),
+    )
+    .await;
+    mount_services(&server, "staging", ResponseTemplate::new(500)).await;
+
+    let topology = client.discover_topology().await.unwrap();
+
+    assert!(!topology.is_complete());
+    assert!(topology.failed_projects.contains_key("shop"));
+    assert!(topology.services.contains_key(&key("production", "api")));
+}
+
+#[tokio::test]
+async fn test_select_matches_names_and_ids() {
+    let server = MockServer::start().await;
+    let client = mock_zeabur(&server).await;
+    for environment in ["production", "staging"] {
+        mount_services(
+            &server,
+            environment,
+            ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "project": { "services": [service("api", Some("RUNNING"))] } }
+            })),
+        )
+        .await;
+    }
+
+    let topology = client.discover_topology().await.unwrap();
+
+    assert_eq!(topology.select(None, None, "api-name").len(), 2);
+    assert!(topology.select(Some("empty"), None, "api").is_empty());
+
+    let selected = topology.select(Some("shop-name"), Some("Staging"), "api");
+    assert_eq!(selected.len(), 1);
+    assert_eq!(selected[0].key, key("staging", "api"));
+}